@@ -0,0 +1,35 @@
+//! The optional JSON config file, `~/.grok.json` unless another is given with `--config`.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::filter::glob_match;
+use crate::format::mapping::FieldMapping;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub mappings: Vec<FieldMapping>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<ConfigFile, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+        let config: ConfigFile = serde_json::from_str(&text).map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+        for mapping in &config.mappings {
+            mapping.validate()?;
+        }
+        Ok(config)
+    }
+
+    /// The mappings named in `names` followed by those whose groups match `group`.
+    pub fn mappings_for(&self, group: &str, names: &[String]) -> Vec<FieldMapping> {
+        let named = self.mappings.iter().filter(|m| names.contains(&m.name));
+        let by_group = self.mappings.iter().filter(|m| {
+            !names.contains(&m.name) && (m.groups.is_empty() || m.groups.iter().any(|g| glob_match(g, group)))
+        });
+        named.chain(by_group).cloned().collect()
+    }
+}
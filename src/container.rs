@@ -0,0 +1,121 @@
+//! Unwrapping of container runtime log files: Docker's json-file driver and the CRI format used
+//! by containerd and CRI-O.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::format::timestamp_str;
+
+fn cri_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(\S+) (stdout|stderr) ([FP]) ?(.*)$").unwrap())
+}
+
+/// One line written by a container, with the runtime's envelope removed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContainerLine {
+    pub time: Option<DateTime<Utc>>,
+    /// `stdout` or `stderr`.
+    pub stream: String,
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+struct DockerRecord {
+    log: String,
+    stream: String,
+    time: Option<String>,
+}
+
+/// A record of either format, and whether it is the last part of its line.
+fn record(line: &str) -> Option<(ContainerLine, bool)> {
+    if line.starts_with("{\"log\":") {
+        let r: DockerRecord = serde_json::from_str(line).ok()?;
+        // lines over 16KB are split into records of which only the last ends with a newline
+        let complete = r.log.ends_with('\n');
+        let text = r.log.trim_end_matches(['\n', '\r']).to_string();
+        return Some((
+            ContainerLine {
+                time: r.time.as_deref().and_then(timestamp_str),
+                stream: r.stream,
+                text,
+            },
+            complete,
+        ));
+    }
+    let c = cri_re().captures(line)?;
+    let time = Some(timestamp_str(&c[1])?);
+    Some((
+        ContainerLine {
+            time,
+            stream: c[2].to_string(),
+            text: c[4].to_string(),
+        },
+        &c[3] == "F",
+    ))
+}
+
+/// Unwraps container log records and joins partial records back into whole lines, separately
+/// for stdout and stderr. Lines that aren't container records are passed through unchanged.
+#[derive(Default)]
+pub struct Unwrapper {
+    partial: HashMap<String, ContainerLine>,
+}
+
+impl Unwrapper {
+    pub fn new() -> Unwrapper {
+        Unwrapper::default()
+    }
+
+    /// Returns the application line once it is complete.
+    pub fn push(&mut self, line: String) -> Option<String> {
+        let Some((record, complete)) = record(&line) else {
+            return Some(line);
+        };
+        let joined = match self.partial.remove(&record.stream) {
+            Some(mut partial) => {
+                partial.text.push_str(&record.text);
+                partial
+            }
+            None => record,
+        };
+        if complete {
+            Some(joined.text)
+        } else {
+            self.partial.insert(joined.stream.clone(), joined);
+            None
+        }
+    }
+
+    /// Returns the partial lines left at the end of the input.
+    pub fn flush(&mut self) -> Vec<String> {
+        let mut partial: Vec<ContainerLine> = self.partial.drain().map(|(_, l)| l).collect();
+        partial.sort_by_key(|l| l.time);
+        partial.into_iter().map(|l| l.text).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Unwrapper;
+
+    #[test]
+    fn unwrap_container_lines() {
+        let mut unwrapper = Unwrapper::new();
+        assert_eq!(
+            unwrapper.push(String::from(r#"{"log":"{\"level\":\"INFO\",\"loggerName\":\"a\"}\n","stream":"stdout","time":"2024-01-01T00:00:00.123456789Z"}"#)),
+            Some(String::from(r#"{"level":"INFO","loggerName":"a"}"#))
+        );
+        assert_eq!(unwrapper.push(String::from(r#"{"log":"hel","stream":"stderr","time":"2024-01-01T00:00:00Z"}"#)), None);
+        assert_eq!(unwrapper.push(String::from("2024-01-01T00:00:00.1Z stdout P par")), None);
+        assert_eq!(unwrapper.push(String::from(r#"{"log":"lo\n","stream":"stderr","time":"2024-01-01T00:00:00Z"}"#)), Some(String::from("hello")));
+        assert_eq!(unwrapper.push(String::from("2024-01-01T00:00:00.2Z stdout F tial")), Some(String::from("partial")));
+        assert_eq!(unwrapper.push(String::from("plain line")), Some(String::from("plain line")));
+        assert_eq!(unwrapper.push(String::from("2024-01-01T00:00:00.3Z stdout P end")), None);
+        assert_eq!(unwrapper.flush(), vec![String::from("end")]);
+    }
+}
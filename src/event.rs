@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+
+/// A log event normalized from any of the supported formats.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogEvent {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub level: Option<String>,
+    pub logger: Option<String>,
+    pub thread: Option<String>,
+    pub message: String,
+    pub fields: Vec<(String, String)>,
+    pub exception: Option<Exception>,
+    /// Access, flow and logfmt records are shown as styled columns in place of the message, which
+    /// then holds the same text unstyled.
+    pub columns: Vec<Column>,
+    /// Where the record says it came from (i.e. an OpenTelemetry `service.name`), shown in place
+    /// of the log group and stream.
+    pub source: Option<String>,
+}
+
+/// A piece of an event's one line summary. The name lets filters refer to it like a field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub name: &'static str,
+    /// Shown before the text as `key=text`, for key/value records whose keys aren't known upfront.
+    pub key: Option<String>,
+    pub text: String,
+    pub style: Style,
+}
+
+impl Column {
+    pub fn new(name: &'static str, text: impl Into<String>, style: Style) -> Column {
+        Column {
+            name,
+            key: None,
+            text: text.into(),
+            style,
+        }
+    }
+
+    pub fn keyed(key: impl Into<String>, text: impl Into<String>, style: Style) -> Column {
+        Column {
+            key: Some(key.into()),
+            ..Column::new("", text, style)
+        }
+    }
+
+    /// The text, quoted logfmt style when it is a keyed value containing spaces or quotes.
+    pub fn value(&self) -> String {
+        if self.key.is_some() && (self.text.is_empty() || self.text.contains([' ', '"', '='])) {
+            format!("{:?}", self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+/// How a column should be emphasized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Style {
+    Plain,
+    Good,
+    Warning,
+    Error,
+    Highlight,
+}
+
+impl LogEvent {
+    /// Looks up `key` among the fields, the named columns and the `level`, `logger`, `thread`
+    /// and `message` attributes.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        if let Some((_, v)) = self.fields.iter().find(|(k, _)| k == key) {
+            return Some(v);
+        }
+        if let Some(c) = self.columns.iter().find(|c| c.name == key || c.key.as_deref() == Some(key)) {
+            return Some(&c.text);
+        }
+        match key {
+            "level" => self.level.as_deref(),
+            "logger" => self.logger.as_deref(),
+            "thread" => self.thread.as_deref(),
+            "message" => Some(&self.message),
+            _ => None,
+        }
+    }
+}
+
+/// An exception with its stack frames, cause chain and suppressed exceptions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Exception {
+    pub name: String,
+    pub message: Option<String>,
+    pub frames: Vec<Frame>,
+    /// Number of trailing frames shared with the enclosing exception's trace.
+    pub common_frames: usize,
+    pub cause: Option<Box<Exception>>,
+    pub suppressed: Vec<Exception>,
+}
+
+/// A single stack frame. `class` is empty for languages without one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    pub class: String,
+    pub method: String,
+    pub file: Option<String>,
+    pub line: Option<i32>,
+    pub location: Option<String>,
+}
+
+impl Frame {
+    /// The fully qualified symbol, i.e. `com.example.Foo.bar` or `bar` when there is no class.
+    pub fn symbol(&self) -> String {
+        if self.class.is_empty() {
+            self.method.clone()
+        } else {
+            format!("{}.{}", self.class, self.method)
+        }
+    }
+}
+
+/// Joins columns into the plain text used as the event's message.
+pub fn columns_text(columns: &[Column]) -> String {
+    let texts: Vec<String> = columns
+        .iter()
+        .map(|c| match &c.key {
+            Some(key) => format!("{}={}", key, c.value()),
+            None => c.text.clone(),
+        })
+        .collect();
+    texts.join(" ")
+}
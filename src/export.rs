@@ -0,0 +1,115 @@
+//! Documents written by `aws logs filter-log-events`, `get-log-events` and `get-query-results`
+//! (CloudWatch Logs Insights), so events shared as JSON can be read back.
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::format::{timestamp_str, timestamp_value};
+
+fn document_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"^\s*\{\s*"(?:events|results)"\s*:"#).unwrap())
+}
+
+/// An event of an exported document.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExportedEvent {
+    /// Only known for Insights results that include `@log`.
+    pub group: Option<String>,
+    pub stream: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FilteredEvent {
+    #[serde(default)]
+    log_stream_name: String,
+    timestamp: Option<i64>,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ResultField {
+    field: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Document {
+    Events { events: Vec<FilteredEvent> },
+    Results { results: Vec<Vec<ResultField>> },
+}
+
+/// Whether the input starting with `head` looks like an exported document rather than log lines.
+pub fn is_export(head: &str) -> bool {
+    document_re().is_match(head)
+}
+
+/// Parses an exported document, or returns `None` when it isn't one.
+pub fn parse(document: &str) -> Option<Vec<ExportedEvent>> {
+    let events = match serde_json::from_str(document).ok()? {
+        Document::Events { events } => events
+            .into_iter()
+            .map(|e| ExportedEvent {
+                group: None,
+                stream: e.log_stream_name,
+                timestamp: e.timestamp.and_then(DateTime::from_timestamp_millis),
+                message: e.message,
+            })
+            .collect(),
+        Document::Results { results } => results.into_iter().map(result_event).collect(),
+    };
+    Some(events)
+}
+
+/// An Insights result row. Without `@message` (a query with `fields`), the other fields are
+/// joined logfmt style.
+fn result_event(row: Vec<ResultField>) -> ExportedEvent {
+    let mut event = ExportedEvent::default();
+    let mut others = vec![];
+    for ResultField { field, value } in row {
+        match field.as_str() {
+            // account id and group, i.e. 123456789012:/aws/lambda/orders
+            "@log" => event.group = Some(value.split_once(':').map_or(value.as_str(), |(_, g)| g).to_string()),
+            "@logStream" => event.stream = value,
+            "@timestamp" => event.timestamp = timestamp_str(&value).or_else(|| timestamp_value(&Value::String(value))),
+            "@message" => event.message = value,
+            "@ptr" => {}
+            _ if value.contains([' ', '"', '=']) => others.push(format!("{}={:?}", field, value)),
+            _ => others.push(format!("{}={}", field, value)),
+        }
+    }
+    if event.message.is_empty() {
+        event.message = others.join(" ");
+    }
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_export, parse};
+
+    #[test]
+    fn parse_exports() {
+        let filtered = "{\n    \"events\": [\n        {\n            \"logStreamName\": \"2024/01/01/[$LATEST]abc\",\n            \"timestamp\": 1704067200000,\n            \"message\": \"START RequestId: 1\",\n            \"ingestionTime\": 1704067200100,\n            \"eventId\": \"1\"\n        }\n    ],\n    \"searchedLogStreams\": []\n}";
+        assert!(is_export(filtered));
+        let events = parse(filtered).unwrap();
+        assert_eq!(events[0].stream, "2024/01/01/[$LATEST]abc");
+        assert_eq!(events[0].message, "START RequestId: 1");
+        assert_eq!(events[0].timestamp.unwrap().timestamp_millis(), 1704067200000);
+
+        let results = r#"{"results":[[{"field":"@timestamp","value":"2024-01-01 00:00:00.000"},{"field":"@log","value":"123456789012:/aws/lambda/orders"},{"field":"@logStream","value":"s1"},{"field":"@message","value":"hello"},{"field":"@ptr","value":"x"}],[{"field":"@timestamp","value":"2024-01-01 00:00:01.000"},{"field":"level","value":"ERROR"},{"field":"msg","value":"it failed"}]],"statistics":{},"status":"Complete"}"#;
+        let events = parse(results).unwrap();
+        assert_eq!(events[0].group.as_deref(), Some("/aws/lambda/orders"));
+        assert_eq!(events[0].message, "hello");
+        assert_eq!(events[1].message, r#"level=ERROR msg="it failed""#);
+        assert!(!is_export(r#"{"level":"INFO","message":"hi"}"#));
+    }
+}
@@ -0,0 +1,329 @@
+//! Filters on the attributes of parsed events, and their translation into CloudWatch filter patterns.
+
+use regex::{Regex, RegexBuilder};
+
+use crate::event::{Exception, LogEvent};
+use crate::level::{self, LevelFilter, LEVELS};
+
+/// `key=value` matching events whose `key` attribute equals `value`, where `*` in the value
+/// matches any run of characters (i.e. `eventName=Delete*`, `principal=*:user/bob`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldFilter {
+    pub key: String,
+    pub value: String,
+}
+
+impl FieldFilter {
+    pub fn parse(spec: &str) -> Result<FieldFilter, String> {
+        match spec.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok(FieldFilter {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(format!("Invalid field filter '{}', expected key=value", spec)),
+        }
+    }
+
+    pub fn matches(&self, event: &LogEvent) -> bool {
+        event.attribute(&self.key).is_some_and(|v| glob_match(&self.value, v))
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` matches any run of characters.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // no wildcard
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// `--grep` and `--grep-v` regexes, checked against the message, logger and field values of an
+/// event, or the whole line when it wasn't parsed. An event is kept when any `--grep` regex
+/// matches and no `--grep-v` regex does.
+#[derive(Clone, Debug, Default)]
+pub struct GrepFilter {
+    patterns: Vec<Regex>,
+    inverted: Vec<Regex>,
+}
+
+impl GrepFilter {
+    pub fn new(patterns: &[String], inverted: &[String], ignore_case: bool) -> Result<GrepFilter, String> {
+        let build = |p: &String| {
+            RegexBuilder::new(p)
+                .case_insensitive(ignore_case)
+                .build()
+                .map_err(|e| e.to_string())
+        };
+        Ok(GrepFilter {
+            patterns: patterns.iter().map(build).collect::<Result<_, _>>()?,
+            inverted: inverted.iter().map(build).collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty() && self.inverted.is_empty()
+    }
+
+    /// Whether the message, logger, field values or exceptions of `event` match.
+    pub fn matches(&self, event: &LogEvent) -> bool {
+        let mut texts: Vec<&str> = [event.message.as_str()]
+            .into_iter()
+            .chain(event.logger.as_deref())
+            .chain(event.fields.iter().map(|(_, v)| v.as_str()))
+            .collect();
+        if let Some(e) = &event.exception {
+            exception_texts(e, &mut texts);
+        }
+        self.matches_texts(&texts)
+    }
+
+    pub fn matches_line(&self, line: &str) -> bool {
+        self.matches_texts(&[line])
+    }
+
+    fn matches_texts(&self, texts: &[&str]) -> bool {
+        let found = |res: &[Regex]| res.iter().any(|re| texts.iter().any(|t| re.is_match(t)));
+        (self.patterns.is_empty() || found(&self.patterns)) && !found(&self.inverted)
+    }
+
+    /// The byte ranges of `text` matched by the `--grep` regexes, sorted and merged.
+    pub fn spans(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut spans: Vec<(usize, usize)> = self
+            .patterns
+            .iter()
+            .flat_map(|re| re.find_iter(text).map(|m| (m.start(), m.end())))
+            .filter(|(start, end)| start < end)
+            .collect();
+        spans.sort();
+        let mut merged: Vec<(usize, usize)> = vec![];
+        for (start, end) in spans {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+}
+
+/// The names and messages of `e`, its causes and its suppressed exceptions.
+fn exception_texts<'a>(e: &'a Exception, texts: &mut Vec<&'a str>) {
+    texts.push(&e.name);
+    texts.extend(e.message.as_deref());
+    for other in e.cause.iter().map(Box::as_ref).chain(&e.suppressed) {
+        exception_texts(other, texts);
+    }
+}
+
+/// JSON paths of the attributes a field filter can be checked against server side. Other keys may
+/// be columns of plain-text records, which a JSON filter pattern would never match.
+const JSON_PATHS: [(&str, &str); 6] = [
+    ("eventName", "$.eventName"),
+    ("eventSource", "$.eventSource"),
+    ("sourceIPAddress", "$.sourceIPAddress"),
+    ("errorCode", "$.errorCode"),
+    ("awsRegion", "$.awsRegion"),
+    ("principal", "$.userIdentity.arn"),
+];
+
+/// CloudWatch Logs rejects longer filter patterns.
+const MAX_PATTERN_LEN: usize = 1024;
+
+/// The level terms of a JSON filter pattern, most useful first: `level` in upper and lower case
+/// with pino's and OTLP's numbers, then python's `levelname` and OTLP's `severityText`, then the
+/// other aliases and title case spellings.
+fn level_terms(filter: &LevelFilter) -> Option<Vec<String>> {
+    let (names, numbers): (Vec<&str>, Vec<String>) = match filter {
+        // every event qualifies, so there is nothing to gain
+        LevelFilter::All => return None,
+        // no event qualifies, which a pattern can't say; nothing downloaded is printed anyway
+        LevelFilter::Off => return None,
+        LevelFilter::AtLeast(min) => {
+            let rank = level::rank(min)?;
+            if rank == 0 {
+                return None;
+            }
+            let numbers = vec![format!("$.level >= {}", (rank + 1) * 10), format!("$.severityNumber >= {}", rank * 4 + 1)];
+            (LEVELS[rank..].to_vec(), numbers)
+        }
+        LevelFilter::Exactly(exact) => {
+            let numbers = match level::rank(exact) {
+                Some(r) => vec![
+                    format!("$.level = {}", (r + 1) * 10),
+                    format!("($.severityNumber >= {} && $.severityNumber <= {})", r * 4 + 1, r * 4 + 4),
+                ],
+                None => vec![],
+            };
+            (vec![exact.as_str()], numbers)
+        }
+    };
+    let mut terms = vec![];
+    for name in &names {
+        terms.push(format!("$.level = \"{}\"", name));
+        terms.push(format!("$.level = \"{}\"", name.to_lowercase()));
+    }
+    terms.extend(numbers);
+    for name in &names {
+        terms.push(format!("$.levelname = \"{}\"", name));
+        terms.push(format!("$.severityText = \"{}\"", name));
+    }
+    let aliases: Vec<String> = names.iter().flat_map(|n| level::aliases(n)).collect();
+    for alias in &aliases {
+        terms.push(format!("$.level = \"{}\"", alias));
+        terms.push(format!("$.level = \"{}\"", alias.to_uppercase()));
+        terms.push(format!("$.levelname = \"{}\"", alias.to_uppercase()));
+    }
+    for alias in &aliases {
+        let mut chars = alias.chars();
+        if let Some(first) = chars.next() {
+            terms.push(format!("$.level = \"{}{}\"", first.to_ascii_uppercase(), chars.as_str()));
+        }
+    }
+    let mut unique: Vec<String> = vec![];
+    for term in terms {
+        if !unique.contains(&term) {
+            unique.push(term);
+        }
+    }
+    Some(unique)
+}
+
+/// Translates the level and field filters into a CloudWatch Logs JSON filter pattern, so fewer
+/// events are downloaded. Field keys without a known JSON path and values with quotes are left
+/// out, and the level terms are cut off at the pattern length CloudWatch accepts; every filter is
+/// still applied after download.
+pub fn cloudwatch_pattern(level: &LevelFilter, fields: &[FieldFilter]) -> Option<String> {
+    let mut conditions = vec![];
+    for field in fields {
+        let path = JSON_PATHS.iter().find(|(k, _)| *k == field.key).map(|(_, p)| p);
+        if let (Some(path), false) = (path, field.value.contains(['"', '\\'])) {
+            conditions.push(format!("{} = \"{}\"", path, field.value));
+        }
+    }
+    if let Some(terms) = level_terms(level) {
+        // the braces, the parentheses and the " && " joining the field conditions
+        let mut len = 6 + conditions.iter().map(|c| c.len() + 4).sum::<usize>();
+        let mut kept = vec![];
+        for term in terms {
+            let added = if kept.is_empty() { term.len() } else { term.len() + 4 };
+            if len + added > MAX_PATTERN_LEN {
+                break;
+            }
+            len += added;
+            kept.push(term);
+        }
+        if !kept.is_empty() {
+            conditions.insert(0, format!("({})", kept.join(" || ")));
+        }
+    }
+    if conditions.is_empty() {
+        return None;
+    }
+    Some(format!("{{ {} }}", conditions.join(" && ")))
+}
+
+/// Combines the user's `--pattern` with a generated JSON pattern. A user pattern that isn't a
+/// JSON pattern can't be combined with one, nor can one that leaves no room for it, so it is
+/// used on its own.
+pub fn merge_patterns(user: Option<&str>, generated: Option<String>) -> Option<String> {
+    let Some(generated) = generated else {
+        return user.map(String::from);
+    };
+    let Some(user) = user else {
+        return Some(generated);
+    };
+    let inner = |p: &str| p.trim().strip_prefix('{')?.strip_suffix('}').map(|i| i.trim().to_string());
+    match (inner(user), inner(&generated)) {
+        (Some(u), Some(g)) if u.len() + g.len() + 10 <= MAX_PATTERN_LEN => Some(format!("{{ ({}) && {} }}", u, g)),
+        _ => Some(user.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cloudwatch_pattern, glob_match, merge_patterns, FieldFilter, GrepFilter};
+    use crate::event::{Exception, LogEvent};
+    use crate::level::LevelFilter;
+
+    #[test]
+    fn match_globs() {
+        assert!(glob_match("DeleteBucket", "DeleteBucket"));
+        assert!(!glob_match("Delete", "DeleteBucket"));
+        assert!(glob_match("Delete*", "DeleteBucket"));
+        assert!(glob_match("*:user/bob", "arn:aws:iam::123456789012:user/bob"));
+        assert!(glob_match("5*", "502"));
+        assert!(glob_match("a*b*a", "aba"));
+        assert!(!glob_match("a*a", "a"));
+        assert!(FieldFilter::parse("status").is_err());
+        assert_eq!(FieldFilter::parse("a=b=c").unwrap().value, "b=c");
+    }
+
+    #[test]
+    fn translate_filters() {
+        let error = LevelFilter::parse("ERROR").unwrap();
+        let fields = vec![FieldFilter::parse("eventName=Delete*").unwrap(), FieldFilter::parse("action=REJECT").unwrap()];
+        let pattern = cloudwatch_pattern(&error, &fields).unwrap();
+        assert!(pattern.starts_with(r#"{ ($.level = "ERROR" || $.level = "error" || $.level = "FATAL" || $.level = "fatal" || $.level >= 50 || $.severityNumber >= 17 || $.levelname = "ERROR" || $.severityText = "ERROR" || "#));
+        assert!(pattern.contains(r#" || $.level = "err" || "#));
+        assert!(pattern.contains(r#" || $.levelname = "CRITICAL" || "#));
+        assert!(pattern.ends_with(r#") && $.eventName = "Delete*" }"#));
+        assert!(pattern.len() <= 1024);
+
+        // the least likely spellings are the ones that don't fit
+        let pattern = cloudwatch_pattern(&LevelFilter::parse("WARN").unwrap(), &[]).unwrap();
+        assert!(pattern.starts_with(r#"{ ($.level = "WARN" || $.level = "warn" || $.level = "ERROR" || $.level = "error" || $.level = "FATAL" || $.level = "fatal" || $.level >= 40 || $.severityNumber >= 13 || "#));
+        assert!(pattern.contains(r#" || $.levelname = "WARNING" || "#));
+        assert!(!pattern.contains(r#"$.level = "Warning""#));
+        assert!(pattern.len() <= 1024);
+
+        assert_eq!(cloudwatch_pattern(&LevelFilter::All, &fields[1..]), None);
+        assert_eq!(cloudwatch_pattern(&LevelFilter::parse("TRACE").unwrap(), &[]), None);
+        assert_eq!(
+            merge_patterns(Some(r#"{ $.requestId = "abc" }"#), cloudwatch_pattern(&LevelFilter::parse("=AUDIT").unwrap(), &[])).as_deref(),
+            Some(r#"{ ($.requestId = "abc") && ($.level = "AUDIT" || $.level = "audit" || $.levelname = "AUDIT" || $.severityText = "AUDIT" || $.level = "Audit") }"#)
+        );
+        assert_eq!(merge_patterns(Some("timeout"), cloudwatch_pattern(&error, &[])).as_deref(), Some("timeout"));
+    }
+
+    #[test]
+    fn grep_events() {
+        let grep = GrepFilter::new(&[String::from("time(out)?"), String::from("out of")], &[String::from("health")], true).unwrap();
+        let event = LogEvent {
+            logger: Some(String::from("com.example.Client")),
+            message: String::from("Request TIMEOUT out of retries"),
+            ..Default::default()
+        };
+        assert!(grep.matches(&event));
+        assert_eq!(grep.spans(&event.message), vec![(8, 15), (16, 22)]);
+        let event = LogEvent {
+            message: String::from("Request failed"),
+            exception: Some(Exception {
+                name: String::from("java.lang.IllegalStateException"),
+                cause: Some(Box::new(Exception {
+                    name: String::from("java.net.SocketTimeoutException"),
+                    message: Some(String::from("Read timed out")),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(grep.matches(&event));
+        assert!(!grep.matches_line("healthcheck timeout"));
+        assert!(!grep.matches_line("all good"));
+        assert!(GrepFilter::new(&[String::from("(")], &[], false).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+use crate::event::{columns_text, Column, LogEvent, Style};
+use crate::format::{field_value, json_object, timestamp_str, timestamp_value, LogFormat};
+
+/// API Gateway access logs written with a JSON `$context` format.
+pub struct ApiGateway;
+
+/// Application Load Balancer access log entries.
+pub struct Alb;
+
+/// Level and style of an HTTP status: 5xx are errors, 4xx warnings.
+fn status_level(status: &str) -> (&'static str, Style) {
+    match status.chars().next() {
+        Some('5') => ("ERROR", Style::Error),
+        Some('4') => ("WARN", Style::Warning),
+        Some('1'..='3') => ("INFO", Style::Good),
+        _ => ("WARN", Style::Warning),
+    }
+}
+
+fn take(obj: &mut Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|k| obj.remove(*k).and_then(field_value))
+}
+
+impl LogFormat for ApiGateway {
+    fn name(&self) -> &str {
+        "apigateway"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let mut obj = json_object(line)?;
+        if !obj.contains_key("httpMethod") || !(obj.contains_key("status") || obj.contains_key("statusCode")) {
+            return None;
+        }
+        let status = take(&mut obj, &["status", "statusCode"])?;
+        let timestamp = match obj.remove("requestTimeEpoch") {
+            Some(epoch) => timestamp_value(&epoch),
+            // $context.requestTime is in Common Log Format
+            None => take(&mut obj, &["requestTime"]).and_then(|t| {
+                DateTime::parse_from_str(&t, "%d/%b/%Y:%H:%M:%S %z").ok().map(|t| t.with_timezone(&Utc))
+            }),
+        };
+        let method = take(&mut obj, &["httpMethod"]).unwrap_or_default();
+        let path = take(&mut obj, &["path", "resourcePath", "routeKey"]).unwrap_or_default();
+        let ip = take(&mut obj, &["ip", "sourceIp"]).unwrap_or_default();
+        let latency = take(&mut obj, &["responseLatency", "latency", "integrationLatency"]);
+        let (level, style) = status_level(&status);
+        let mut columns = vec![
+            Column::new("status", status, style),
+            Column::new("method", method, Style::Plain),
+            Column::new("path", path, Style::Plain),
+            Column::new("ip", ip, Style::Highlight),
+        ];
+        if let Some(latency) = latency {
+            columns.push(Column::new("latency", format!("{} ms", latency), Style::Plain));
+        }
+        let fields = obj
+            .into_iter()
+            .filter_map(|(k, v)| field_value(v).filter(|v| v != "-").map(|v| (k, v)))
+            .collect();
+        Some(LogEvent {
+            timestamp,
+            level: Some(level.to_string()),
+            logger: Some(String::from("apigateway")),
+            message: columns_text(&columns),
+            fields,
+            columns,
+            ..Default::default()
+        })
+    }
+}
+
+/// Splits a space delimited line, keeping double quoted values together.
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ' ' if !quoted => tokens.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    tokens.push(current);
+    tokens
+}
+
+impl LogFormat for Alb {
+    fn name(&self) -> &str {
+        "alb"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let t = tokenize(line.trim());
+        if t.len() < 17 || !matches!(t[0].as_str(), "http" | "https" | "h2" | "grpcs" | "ws" | "wss") {
+            return None;
+        }
+        let timestamp = timestamp_str(&t[1])?;
+        let client = t[3].rsplit_once(':').map_or(t[3].as_str(), |(ip, _)| ip);
+        let elb_status = &t[8];
+        // request, target and response processing times in seconds; -1 when the target never answered
+        let latency: f64 = t[5..8].iter().filter_map(|s| s.parse::<f64>().ok()).filter(|s| *s >= 0.0).sum();
+        let (level, style) = status_level(elb_status);
+        let columns = vec![
+            Column::new("status", elb_status.clone(), style),
+            Column::new("request", t[12].clone(), Style::Plain),
+            Column::new("ip", client, Style::Highlight),
+            Column::new("latency", format!("{:.0} ms", latency * 1000.0), Style::Plain),
+        ];
+        let mut fields = vec![
+            (String::from("target"), t[4].clone()),
+            (String::from("target_status_code"), t[9].clone()),
+            (String::from("user_agent"), t[13].clone()),
+        ];
+        if let Some(trace) = t.get(17) {
+            fields.push((String::from("trace_id"), trace.clone()));
+        }
+        if let Some(reason) = t.get(24) {
+            fields.push((String::from("error_reason"), reason.clone()));
+        }
+        fields.retain(|(_, v)| v != "-");
+        Some(LogEvent {
+            timestamp: Some(timestamp),
+            level: Some(level.to_string()),
+            logger: Some(t[2].clone()),
+            message: columns_text(&columns),
+            fields,
+            columns,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alb, ApiGateway};
+    use crate::format::LogFormat;
+
+    #[test]
+    fn parse_access_logs() {
+        let event = ApiGateway
+            .parse(r#"{"requestId":"c6af9ac6","ip":"192.0.2.1","requestTime":"21/Dec/2020:19:38:28 +0000","httpMethod":"POST","resourcePath":"/orders","status":"502","protocol":"HTTP/1.1","responseLength":"36","responseLatency":"29012"}"#)
+            .unwrap();
+        assert_eq!(event.level.as_deref(), Some("ERROR"));
+        assert_eq!(event.message, "502 POST /orders 192.0.2.1 29012 ms");
+        assert!(event.timestamp.is_some());
+
+        let event = Alb
+            .parse(r#"https 2018-07-02T22:23:00.186641Z app/my-loadbalancer/50dc6c495c0c9188 192.168.131.39:2817 10.0.0.1:80 0.086 0.048 0.037 404 404 34 366 "GET https://www.example.com:443/missing HTTP/1.1" "curl/7.46.0" ECDHE-RSA-AES128-GCM-SHA256 TLSv1.2 arn:aws:elasticloadbalancing:us-east-2:123456789012:targetgroup/my-targets/73e2d6bc24d8a067 "Root=1-58337281-1d84f3d73c47ec4e58577259" "www.example.com" "-" 1 2018-07-02T22:22:48.364000Z "forward" "-" "-" "10.0.0.1:80" "404" "-" "-""#)
+            .unwrap();
+        assert_eq!(event.level.as_deref(), Some("WARN"));
+        assert_eq!(event.message, "404 GET https://www.example.com:443/missing HTTP/1.1 192.168.131.39 171 ms");
+        assert_eq!(event.logger.as_deref(), Some("app/my-loadbalancer/50dc6c495c0c9188"));
+    }
+}
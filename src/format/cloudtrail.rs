@@ -0,0 +1,84 @@
+use serde_json::Value;
+
+use crate::event::{columns_text, Column, LogEvent, Style};
+use crate::format::{field_value, json_object, timestamp_value, LogFormat};
+
+/// CloudTrail events delivered to CloudWatch Logs, one concise line per API call.
+pub struct CloudTrail;
+
+/// The identity that made the call: its ARN, or the service or principal id when there is none.
+fn principal(identity: &Value) -> String {
+    ["arn", "invokedBy", "principalId", "type"]
+        .iter()
+        .find_map(|k| identity[*k].as_str())
+        .unwrap_or("-")
+        .to_string()
+}
+
+impl LogFormat for CloudTrail {
+    fn name(&self) -> &str {
+        "cloudtrail"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let mut obj = json_object(line)?;
+        if !obj.contains_key("eventVersion") || !obj.contains_key("eventName") {
+            return None;
+        }
+        let mut take = |key: &str| obj.remove(key).and_then(field_value);
+        let timestamp = take("eventTime").and_then(|t| timestamp_value(&Value::String(t)));
+        let source = take("eventSource").unwrap_or_default();
+        let name = take("eventName").unwrap_or_default();
+        let ip = take("sourceIPAddress").unwrap_or_default();
+        let error_code = take("errorCode");
+        let error_message = take("errorMessage");
+        let region = take("awsRegion");
+        let principal = principal(obj.get("userIdentity").unwrap_or(&Value::Null));
+        let mut columns = vec![
+            Column::new("eventSource", source, Style::Plain),
+            Column::new("eventName", name, Style::Highlight),
+            Column::new("principal", principal, Style::Plain),
+            Column::new("sourceIPAddress", ip, Style::Plain),
+        ];
+        let level = match &error_code {
+            Some(code) => {
+                columns.push(Column::new("errorCode", code.clone(), Style::Error));
+                "ERROR"
+            }
+            None => "INFO",
+        };
+        let mut fields = vec![];
+        if let Some(message) = error_message {
+            fields.push((String::from("errorMessage"), message));
+        }
+        if let Some(region) = region {
+            fields.push((String::from("awsRegion"), region));
+        }
+        Some(LogEvent {
+            timestamp,
+            level: Some(level.to_string()),
+            logger: Some(String::from("cloudtrail")),
+            message: columns_text(&columns),
+            fields,
+            columns,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CloudTrail;
+    use crate::format::LogFormat;
+
+    #[test]
+    fn parse_cloudtrail_event() {
+        let event = CloudTrail
+            .parse(r#"{"eventVersion":"1.08","userIdentity":{"type":"AssumedRole","principalId":"AROAEXAMPLE:bob","arn":"arn:aws:sts::123456789012:assumed-role/Admin/bob","accountId":"123456789012"},"eventTime":"2020-12-21T19:38:28Z","eventSource":"s3.amazonaws.com","eventName":"DeleteBucket","awsRegion":"us-east-1","sourceIPAddress":"203.0.113.5","userAgent":"aws-cli/2.0","errorCode":"AccessDenied","errorMessage":"Access Denied","requestParameters":{"bucketName":"orders"},"eventID":"1","readOnly":false}"#)
+            .unwrap();
+        assert_eq!(event.level.as_deref(), Some("ERROR"));
+        assert_eq!(event.message, "s3.amazonaws.com DeleteBucket arn:aws:sts::123456789012:assumed-role/Admin/bob 203.0.113.5 AccessDenied");
+        assert_eq!(event.attribute("principal"), Some("arn:aws:sts::123456789012:assumed-role/Admin/bob"));
+        assert_eq!(event.attribute("errorMessage"), Some("Access Denied"));
+    }
+}
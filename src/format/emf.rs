@@ -0,0 +1,149 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+use crate::event::LogEvent;
+use crate::format::{field_value, json_object, timestamp_value, LogFormat};
+
+/// One metric of an Embedded Metric Format record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metric {
+    pub name: String,
+    pub unit: Option<String>,
+    pub values: Vec<f64>,
+}
+
+/// One `CloudWatchMetrics` directive of an EMF record with the values it refers to resolved. A
+/// directive with several dimension sets yields one record per set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricRecord {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub namespace: String,
+    pub dimensions: Vec<(String, String)>,
+    pub metrics: Vec<Metric>,
+}
+
+impl MetricRecord {
+    /// Parses the metrics of an EMF line, or `None` when the line has no `_aws` metadata.
+    pub fn parse(line: &str) -> Option<Vec<MetricRecord>> {
+        let obj = json_object(line)?;
+        let aws = obj.get("_aws")?.as_object()?;
+        let directives = aws.get("CloudWatchMetrics")?.as_array()?;
+        let timestamp = aws.get("Timestamp").and_then(timestamp_value);
+        let mut records = vec![];
+        for directive in directives {
+            let namespace = directive["Namespace"].as_str().unwrap_or_default().to_string();
+            let metrics: Vec<Metric> = directive["Metrics"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|m| {
+                    let name = m["Name"].as_str()?.to_string();
+                    Some(Metric {
+                        values: values(obj.get(&name)),
+                        unit: m["Unit"].as_str().map(String::from),
+                        name,
+                    })
+                })
+                .collect();
+            let dimension_sets: Vec<&Vec<Value>> = directive["Dimensions"].as_array().into_iter().flatten().filter_map(Value::as_array).collect();
+            if dimension_sets.is_empty() {
+                records.push(MetricRecord {
+                    timestamp,
+                    namespace: namespace.clone(),
+                    dimensions: vec![],
+                    metrics: metrics.clone(),
+                });
+            }
+            for set in dimension_sets {
+                records.push(MetricRecord {
+                    timestamp,
+                    namespace: namespace.clone(),
+                    dimensions: dimensions(&obj, set),
+                    metrics: metrics.clone(),
+                });
+            }
+        }
+        Some(records)
+    }
+}
+
+fn values(v: Option<&Value>) -> Vec<f64> {
+    match v {
+        Some(Value::Number(n)) => n.as_f64().into_iter().collect(),
+        Some(Value::Array(a)) => a.iter().filter_map(Value::as_f64).collect(),
+        _ => vec![],
+    }
+}
+
+fn dimensions(obj: &Map<String, Value>, set: &[Value]) -> Vec<(String, String)> {
+    set.iter()
+        .filter_map(Value::as_str)
+        .map(|d| (d.to_string(), obj.get(d).cloned().and_then(field_value).unwrap_or_default()))
+        .collect()
+}
+
+/// CloudWatch Embedded Metric Format records, shown as a one line summary instead of the blob.
+pub struct Emf;
+
+impl LogFormat for Emf {
+    fn name(&self) -> &str {
+        "emf"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let records = MetricRecord::parse(line)?;
+        let summaries: Vec<String> = records.iter().map(summary).collect();
+        Some(LogEvent {
+            timestamp: records.first().and_then(|r| r.timestamp),
+            level: Some(String::from("INFO")),
+            logger: Some(records.iter().map(|r| r.namespace.as_str()).collect::<Vec<_>>().join(",")),
+            message: summaries.join("; "),
+            ..Default::default()
+        })
+    }
+}
+
+/// i.e. `Service=orders Operation=put: Latency=12.5 Milliseconds, Errors=0`.
+fn summary(r: &MetricRecord) -> String {
+    let dimensions: Vec<String> = r.dimensions.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    let metrics: Vec<String> = r
+        .metrics
+        .iter()
+        .map(|m| {
+            let values: Vec<String> = m.values.iter().map(f64::to_string).collect();
+            match &m.unit {
+                Some(unit) if unit != "None" => format!("{}={} {}", m.name, values.join(","), unit),
+                _ => format!("{}={}", m.name, values.join(",")),
+            }
+        })
+        .collect();
+    if dimensions.is_empty() {
+        metrics.join(", ")
+    } else {
+        format!("{}: {}", dimensions.join(" "), metrics.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Metric, MetricRecord};
+
+    #[test]
+    fn parse_emf() {
+        let line = r#"{"_aws":{"Timestamp":1608579508964,"CloudWatchMetrics":[{"Namespace":"Orders","Dimensions":[["Service"],["Service","Operation"]],"Metrics":[{"Name":"Latency","Unit":"Milliseconds"}]}]},"Service":"orders","Operation":"put","Latency":[12.5,3],"requestId":"abc"}"#;
+
+        let records = MetricRecord::parse(line).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].namespace, "Orders");
+        assert_eq!(records[1].dimensions, vec![("Service".to_string(), "orders".to_string()), ("Operation".to_string(), "put".to_string())]);
+        assert_eq!(
+            records[1].metrics,
+            vec![Metric {
+                name: "Latency".to_string(),
+                unit: Some("Milliseconds".to_string()),
+                values: vec![12.5, 3.0],
+            }]
+        );
+        assert!(MetricRecord::parse(r#"{"level":"info"}"#).is_none());
+    }
+}
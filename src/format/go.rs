@@ -0,0 +1,130 @@
+use serde_json::{Map, Value};
+
+use crate::event::{Exception, Frame, LogEvent};
+use crate::format::{field_value, json_object, stacktrace, timestamp_value, LogFormat};
+use crate::level;
+
+/// go.uber.org/zap JSON records.
+pub struct Zap;
+
+/// github.com/rs/zerolog JSON records.
+pub struct Zerolog;
+
+/// github.com/sirupsen/logrus `JSONFormatter` records.
+pub struct Logrus;
+
+/// Keys that locate the record; shared by all three libraries with different names.
+struct Keys {
+    time: &'static str,
+    message: &'static str,
+    caller: &'static str,
+}
+
+fn event(mut obj: Map<String, Value>, keys: Keys, frames: Vec<Frame>) -> LogEvent {
+    let timestamp = obj.remove(keys.time).as_ref().and_then(timestamp_value);
+    let level = obj.remove("level").and_then(field_value).map(|l| level::normalize(&l));
+    let message = obj.remove(keys.message).and_then(field_value).unwrap_or_default();
+    let mut logger = obj.remove(keys.caller).and_then(field_value);
+    if logger.is_none() {
+        logger = obj.remove("logger").and_then(field_value);
+    }
+    let exception = if frames.is_empty() {
+        None
+    } else {
+        Some(Exception {
+            name: String::from("error"),
+            message: obj.remove("error").and_then(field_value),
+            frames,
+            ..Default::default()
+        })
+    };
+    let fields = obj
+        .into_iter()
+        .filter_map(|(k, v)| field_value(v).map(|v| (k, v)))
+        .collect();
+    LogEvent {
+        timestamp,
+        level,
+        logger,
+        thread: None,
+        message,
+        fields,
+        exception,
+        ..Default::default()
+    }
+}
+
+/// A JSON object with a string `level` and all of `keys`.
+fn object_with(line: &str, keys: &[&str]) -> Option<Map<String, Value>> {
+    let obj = json_object(line)?;
+    if !obj.get("level")?.is_string() || !keys.iter().all(|k| obj.contains_key(*k)) {
+        return None;
+    }
+    Some(obj)
+}
+
+impl LogFormat for Zap {
+    fn name(&self) -> &str {
+        "zap"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let mut obj = object_with(line, &["ts", "msg"])?;
+        let frames = obj
+            .remove("stacktrace")
+            .and_then(field_value)
+            .map(|st| stacktrace::parse_go(&st))
+            .unwrap_or_default();
+        let keys = Keys {
+            time: "ts",
+            message: "msg",
+            caller: "caller",
+        };
+        Some(event(obj, keys, frames))
+    }
+}
+
+impl LogFormat for Zerolog {
+    fn name(&self) -> &str {
+        "zerolog"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let mut obj = object_with(line, &["message"])?;
+        // pkgerrors.MarshalStack writes [{"func": ..., "line": ..., "source": ...}]
+        let frames = match obj.remove("stack") {
+            Some(Value::Array(stack)) => stack
+                .iter()
+                .map(|f| {
+                    let mut frame = stacktrace::go_frame(f["func"].as_str().unwrap_or_default());
+                    frame.file = f["source"].as_str().map(String::from);
+                    frame.line = f["line"].as_str().and_then(|l| l.parse().ok());
+                    frame
+                })
+                .collect(),
+            _ => vec![],
+        };
+        let keys = Keys {
+            time: "time",
+            message: "message",
+            caller: "caller",
+        };
+        Some(event(obj, keys, frames))
+    }
+}
+
+impl LogFormat for Logrus {
+    fn name(&self) -> &str {
+        "logrus"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let obj = object_with(line, &["time", "msg"])?;
+        let keys = Keys {
+            time: "time",
+            message: "msg",
+            caller: "file",
+        };
+        Some(event(obj, keys, vec![]))
+    }
+}
@@ -0,0 +1,157 @@
+use crate::event::LogEvent;
+use crate::format::LogFormat;
+
+/// The `REPORT` line Lambda writes at the end of every invocation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub request_id: String,
+    pub duration_ms: f64,
+    pub billed_duration_ms: f64,
+    pub memory_size_mb: u64,
+    pub max_memory_used_mb: u64,
+    /// Only present on the first invocation of an execution environment (a cold start).
+    pub init_duration_ms: Option<f64>,
+}
+
+impl Report {
+    pub fn cold_start(&self) -> bool {
+        self.init_duration_ms.is_some()
+    }
+}
+
+/// A line written by the Lambda platform rather than the function.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlatformRecord {
+    InitStart { runtime_version: String },
+    Start { request_id: String, version: String },
+    End { request_id: String },
+    Report(Report),
+}
+
+impl PlatformRecord {
+    pub fn parse(line: &str) -> Option<PlatformRecord> {
+        let line = line.trim_end();
+        if let Some(rest) = line.strip_prefix("INIT_START ") {
+            let runtime_version = attribute(rest, "Runtime Version").unwrap_or_default();
+            return Some(PlatformRecord::InitStart { runtime_version });
+        }
+        if let Some(rest) = line.strip_prefix("START ") {
+            return Some(PlatformRecord::Start {
+                request_id: attribute(rest, "RequestId")?,
+                version: attribute(rest, "Version").unwrap_or_default(),
+            });
+        }
+        if let Some(rest) = line.strip_prefix("END ") {
+            return Some(PlatformRecord::End {
+                request_id: attribute(rest, "RequestId")?,
+            });
+        }
+        let rest = line.strip_prefix("REPORT ")?;
+        let number = |key: &str| attribute(rest, key).and_then(|v| v.split(' ').next()?.parse::<f64>().ok());
+        Some(PlatformRecord::Report(Report {
+            request_id: attribute(rest, "RequestId")?,
+            duration_ms: number("Duration")?,
+            billed_duration_ms: number("Billed Duration").unwrap_or_default(),
+            memory_size_mb: number("Memory Size").unwrap_or_default() as u64,
+            max_memory_used_mb: number("Max Memory Used").unwrap_or_default() as u64,
+            init_duration_ms: number("Init Duration"),
+        }))
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            PlatformRecord::InitStart { .. } => None,
+            PlatformRecord::Start { request_id, .. } | PlatformRecord::End { request_id } => Some(request_id),
+            PlatformRecord::Report(r) => Some(&r.request_id),
+        }
+    }
+}
+
+/// Reads the value of `key: value` from a platform line. Attributes are tab separated, except on
+/// START lines where they are separated by spaces.
+fn attribute(line: &str, key: &str) -> Option<String> {
+    let prefix = format!("{}: ", key);
+    let value = match line.split('\t').map(str::trim).find(|p| p.starts_with(&prefix)) {
+        Some(part) => &part[prefix.len()..],
+        None => &line[line.find(&format!(" {}", prefix))? + prefix.len() + 1..],
+    };
+    // cut off any attribute that follows on the same (space separated) part
+    let value = match value.find(": ") {
+        Some(i) => value[..i].rsplit_once(' ').map_or(&value[..i], |(v, _)| v),
+        None => value,
+    };
+    Some(value.trim().to_string())
+}
+
+/// Lambda platform `INIT_START`, `START`, `END` and `REPORT` lines.
+pub struct LambdaPlatform;
+
+impl LogFormat for LambdaPlatform {
+    fn name(&self) -> &str {
+        "lambda"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let record = PlatformRecord::parse(line)?;
+        let mut fields = vec![];
+        if let Some(id) = record.request_id() {
+            fields.push((String::from("requestId"), id.to_string()));
+        }
+        let message = match &record {
+            PlatformRecord::InitStart { runtime_version } => format!("INIT_START {}", runtime_version),
+            PlatformRecord::Start { version, .. } => format!("START {}", version),
+            PlatformRecord::End { .. } => String::from("END"),
+            PlatformRecord::Report(r) => {
+                fields.push((String::from("maxMemoryUsed"), format!("{} MB", r.max_memory_used_mb)));
+                summary(r)
+            }
+        };
+        Some(LogEvent {
+            level: Some(String::from("INFO")),
+            logger: Some(String::from("lambda")),
+            message,
+            fields,
+            ..Default::default()
+        })
+    }
+}
+
+/// A one line summary of an invocation, i.e. `REPORT 102.25 ms (billed 103 ms), 70/128 MB`.
+fn summary(r: &Report) -> String {
+    let mut s = format!(
+        "REPORT {} ms (billed {} ms), {}/{} MB",
+        r.duration_ms, r.billed_duration_ms, r.max_memory_used_mb, r.memory_size_mb
+    );
+    if let Some(init) = r.init_duration_ms {
+        s.push_str(&format!(", cold start (init {} ms)", init));
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlatformRecord, Report};
+
+    #[test]
+    fn parse_platform_lines() {
+        assert_eq!(
+            PlatformRecord::parse("START RequestId: 8f5b7b6e-1d3c-4c1e-9b1e-0c2b8d1c2f3a Version: $LATEST"),
+            Some(PlatformRecord::Start {
+                request_id: String::from("8f5b7b6e-1d3c-4c1e-9b1e-0c2b8d1c2f3a"),
+                version: String::from("$LATEST"),
+            })
+        );
+        assert_eq!(
+            PlatformRecord::parse("REPORT RequestId: 8f5b7b6e-1d3c-4c1e-9b1e-0c2b8d1c2f3a\tDuration: 102.25 ms\tBilled Duration: 103 ms\tMemory Size: 128 MB\tMax Memory Used: 70 MB\tInit Duration: 150.31 ms\t\n"),
+            Some(PlatformRecord::Report(Report {
+                request_id: String::from("8f5b7b6e-1d3c-4c1e-9b1e-0c2b8d1c2f3a"),
+                duration_ms: 102.25,
+                billed_duration_ms: 103.0,
+                memory_size_mb: 128,
+                max_memory_used_mb: 70,
+                init_duration_ms: Some(150.31),
+            }))
+        );
+        assert_eq!(PlatformRecord::parse("STARTING the app"), None);
+    }
+}
@@ -0,0 +1,330 @@
+use chrono::{DateTime, NaiveDateTime, NaiveTime, Utc};
+use regex::Regex;
+
+use crate::event::LogEvent;
+use crate::format::{stacktrace, timestamp_str, LogFormat};
+use crate::level;
+
+/// What a capture group of the compiled layout holds.
+enum Role {
+    /// The chrono format of the date, when the layout's date pattern could be translated.
+    Timestamp(Option<String>),
+    Level,
+    Logger,
+    Thread,
+    Message,
+    Field(String),
+}
+
+/// A plain-text format described by a Log4j/Logback `PatternLayout` conversion pattern, e.g.
+/// `%d{ISO8601} [%t] %-5level %logger{36} - %msg%n`.
+pub struct PatternLayout {
+    pattern: String,
+    regex: Regex,
+    roles: Vec<Role>,
+}
+
+impl PatternLayout {
+    /// Compiles `pattern`, failing on unbalanced braces or a date pattern that doesn't compile.
+    pub fn new(pattern: &str) -> Result<PatternLayout, String> {
+        let mut compiler = Compiler::default();
+        compiler.compile(pattern)?;
+        let regex = Regex::new(&format!("(?s)^{}$", compiler.regex))
+            .map_err(|e| format!("invalid layout '{}': {}", pattern, e))?;
+        Ok(PatternLayout {
+            pattern: pattern.to_string(),
+            regex,
+            roles: compiler.roles,
+        })
+    }
+}
+
+impl LogFormat for PatternLayout {
+    fn name(&self) -> &str {
+        &self.pattern
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let caps = self.regex.captures(line)?;
+        let mut event = LogEvent::default();
+        for (i, role) in self.roles.iter().enumerate() {
+            let value = match caps.get(i + 1) {
+                Some(m) => m.as_str().trim(),
+                None => continue,
+            };
+            match role {
+                Role::Timestamp(format) => event.timestamp = parse_date(value, format.as_deref()),
+                Role::Level => event.level = Some(level::normalize(value)),
+                Role::Logger => event.logger = Some(value.to_string()),
+                Role::Thread => event.thread = Some(value.to_string()),
+                Role::Message => event.message = value.to_string(),
+                Role::Field(key) => {
+                    if !value.is_empty() {
+                        event.fields.push((key.clone(), value.to_string()))
+                    }
+                }
+            }
+        }
+        // a stack trace printed after the message (%ex, or the default when %ex is absent)
+        if let Some((message, rest)) = event.message.split_once('\n') {
+            let exception = stacktrace::parse_java(rest).or_else(|| stacktrace::parse_python(rest));
+            if exception.is_some() {
+                event.exception = exception;
+                event.message = message.trim_end().to_string();
+            }
+        }
+        Some(event)
+    }
+}
+
+fn parse_date(value: &str, format: Option<&str>) -> Option<DateTime<Utc>> {
+    if let Some(format) = format {
+        if format.contains("%z") || format.contains("%:z") {
+            if let Ok(ts) = DateTime::parse_from_str(value, format) {
+                return Some(ts.with_timezone(&Utc));
+            }
+        } else if format.contains("%Y") || format.contains("%y") {
+            if let Ok(ts) = NaiveDateTime::parse_from_str(value, format) {
+                return Some(ts.and_utc());
+            }
+        } else if let Ok(time) = NaiveTime::parse_from_str(value, format) {
+            // time-only layouts such as %d{ABSOLUTE} are taken to be from today
+            return Some(Utc::now().date_naive().and_time(time).and_utc());
+        }
+    }
+    match value.parse::<i64>() {
+        Ok(n) if n > 100_000_000_000 => DateTime::from_timestamp_millis(n),
+        Ok(n) => DateTime::from_timestamp(n, 0),
+        Err(_) => timestamp_str(value),
+    }
+}
+
+#[derive(Default)]
+struct Compiler {
+    regex: String,
+    roles: Vec<Role>,
+}
+
+impl Compiler {
+    fn compile(&mut self, pattern: &str) -> Result<(), String> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            i += 1;
+            if c != '%' {
+                self.literal(c);
+                continue;
+            }
+            if i < chars.len() && chars[i] == '%' {
+                self.literal('%');
+                i += 1;
+                continue;
+            }
+            // format modifiers (%-5level, %20.-30logger) only pad or truncate
+            let mut padded = false;
+            while i < chars.len() && (chars[i] == '-' || chars[i] == '.' || chars[i].is_ascii_digit()) {
+                padded = true;
+                i += 1;
+            }
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            // Spring Boot's %clr(...) wraps a nested pattern in parentheses
+            let mut nested = None;
+            if i < chars.len() && chars[i] == '(' {
+                nested = Some(read_group(&chars, &mut i, '(', ')', pattern)?);
+            }
+            let mut options = vec![];
+            while i < chars.len() && chars[i] == '{' {
+                options.push(read_group(&chars, &mut i, '{', '}', pattern)?);
+            }
+            if let Some(inner) = nested {
+                self.compile(&inner)?;
+                continue;
+            }
+            let option = options.first().map(String::as_str);
+            let pad = if padded { r"\s*" } else { "" };
+            match name.as_str() {
+                "d" | "date" => {
+                    let (regex, format) = date_pattern(option.unwrap_or("DEFAULT"));
+                    self.capture(&regex, Role::Timestamp(format), "");
+                }
+                "p" | "le" | "level" => self.capture("[A-Za-z]+", Role::Level, pad),
+                "c" | "lo" | "logger" => self.capture(r"\S+?", Role::Logger, pad),
+                "t" | "tn" | "thread" | "threadName" => self.capture(".*?", Role::Thread, pad),
+                "m" | "msg" | "message" => self.capture(".*", Role::Message, ""),
+                "X" | "mdc" | "MDC" => {
+                    let key = option.unwrap_or("mdc").to_string();
+                    self.capture(".*?", Role::Field(key), pad)
+                }
+                "C" | "class" => self.capture(r"\S+?", Role::Field(String::from("class")), pad),
+                "M" | "method" => self.capture(r"\S+?", Role::Field(String::from("method")), pad),
+                "F" | "file" => self.capture(r"\S+?", Role::Field(String::from("file")), pad),
+                "L" | "line" => self.capture(r"\d*", Role::Field(String::from("line")), pad),
+                "r" | "relative" => self.capture(r"\d+", Role::Field(String::from("relative")), pad),
+                "pid" | "processId" => self.capture(r"\d+", Role::Field(String::from("pid")), pad),
+                "T" | "tid" | "threadId" => self.capture(r"\d+", Role::Field(String::from("threadId")), pad),
+                "marker" | "markerSimpleName" => {
+                    self.capture(".*?", Role::Field(String::from("marker")), pad)
+                }
+                "highlight" | "style" => {
+                    if let Some(inner) = option {
+                        self.compile(inner)?;
+                    }
+                }
+                // stack traces follow the message on later lines; %n ends the line
+                "n" | "ex" | "exception" | "throwable" | "xEx" | "xException" | "xThrowable"
+                | "rEx" | "rException" | "rThrowable" | "wEx" | "wex" | "nopex" => {}
+                _ => self.regex.push_str(r".*?"),
+            }
+        }
+        Ok(())
+    }
+
+    fn literal(&mut self, c: char) {
+        if c.is_whitespace() {
+            if !self.regex.ends_with(r"\s+") {
+                self.regex.push_str(r"\s+");
+            }
+        } else {
+            self.regex.push_str(&regex::escape(&c.to_string()));
+        }
+    }
+
+    fn capture(&mut self, regex: &str, role: Role, pad: &str) {
+        self.regex.push_str(&format!("{}({}){}", pad, regex, pad));
+        self.roles.push(role);
+    }
+}
+
+/// Reads a `{...}` or `(...)` group starting at `chars[*i]`, honoring nesting.
+fn read_group(chars: &[char], i: &mut usize, open: char, close: char, pattern: &str) -> Result<String, String> {
+    let start = *i + 1;
+    let mut depth = 0;
+    while *i < chars.len() {
+        if chars[*i] == open {
+            depth += 1;
+        } else if chars[*i] == close {
+            depth -= 1;
+            if depth == 0 {
+                *i += 1;
+                return Ok(chars[start..*i - 1].iter().collect());
+            }
+        }
+        *i += 1;
+    }
+    Err(format!("invalid layout '{}': unbalanced '{}'", pattern, open))
+}
+
+/// Translates a `%d{...}` option (a named format or a `SimpleDateFormat` pattern) into a regex
+/// and, where possible, a chrono format to parse the matched text with.
+fn date_pattern(option: &str) -> (String, Option<String>) {
+    let java = match option {
+        "DEFAULT" => "yyyy-MM-dd HH:mm:ss,SSS",
+        "ISO8601" => "yyyy-MM-dd'T'HH:mm:ss,SSS",
+        "ISO8601_OFFSET_DATE_TIME_HHMM" => "yyyy-MM-dd'T'HH:mm:ss,SSSZ",
+        "ISO8601_OFFSET_DATE_TIME_HHCMM" => "yyyy-MM-dd'T'HH:mm:ss,SSSXXX",
+        "ISO8601_BASIC" => "yyyyMMdd'T'HHmmss,SSS",
+        "ABSOLUTE" => "HH:mm:ss,SSS",
+        "DATE" => "dd MMM yyyy HH:mm:ss,SSS",
+        "COMPACT" => "yyyyMMddHHmmssSSS",
+        "UNIX" | "UNIX_MILLIS" => return (String::from(r"\d+"), None),
+        other => other,
+    };
+    let chars: Vec<char> = java.chars().collect();
+    let mut regex = String::new();
+    let mut format = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\'' {
+            let end = chars[i + 1..].iter().position(|&q| q == '\'').map_or(chars.len(), |p| i + 1 + p);
+            let quoted: String = chars[i + 1..end].iter().collect();
+            if quoted == "T" {
+                // log4j writes ISO8601 with a 'T', logback with a space
+                regex.push_str("[T ]");
+            } else {
+                regex.push_str(&regex::escape(&quoted));
+            }
+            format.push_str(&quoted.replace('%', "%%"));
+            i = end + 1;
+            continue;
+        }
+        let mut n = 1;
+        while i + n < chars.len() && chars[i + n] == c {
+            n += 1;
+        }
+        i += n;
+        let (r, f) = match (c, n) {
+            ('y' | 'u', 2) => (r"\d{2}", "%y"),
+            ('y' | 'u', _) => (r"\d{4}", "%Y"),
+            ('M', 1) => (r"\d{1,2}", "%m"),
+            ('M', 2) => (r"\d{2}", "%m"),
+            ('M', 3) => ("[A-Za-z]{3}", "%b"),
+            ('M', _) => ("[A-Za-z]+", "%B"),
+            ('d', 1) => (r"\d{1,2}", "%d"),
+            ('d', _) => (r"\d{2}", "%d"),
+            ('H', 1) => (r"\d{1,2}", "%H"),
+            ('H', _) => (r"\d{2}", "%H"),
+            ('h', _) => (r"\d{1,2}", "%I"),
+            ('m', _) => (r"\d{2}", "%M"),
+            ('s', _) => (r"\d{2}", "%S"),
+            ('S', 3) => (r"\d{3}", "%3f"),
+            ('S', 6) => (r"\d{6}", "%6f"),
+            ('S', 9) => (r"\d{9}", "%9f"),
+            ('S', _) => (r"\d+", ""),
+            ('a', _) => ("[AaPp][Mm]", "%p"),
+            ('E', 1..=3) => ("[A-Za-z]{3}", "%a"),
+            ('E', _) => ("[A-Za-z]+", "%A"),
+            ('Z', _) => (r"[+-]\d{4}", "%z"),
+            ('X', _) => (r"(?:Z|[+-]\d{2}(?::?\d{2})?)", "%:z"),
+            ('z', _) => (r"[A-Za-z]+", ""),
+            _ => {
+                let literal: String = std::iter::repeat_n(c, n).collect();
+                regex.push_str(&regex::escape(&literal));
+                format.push_str(&literal.replace('%', "%%"));
+                continue;
+            }
+        };
+        if f.is_empty() {
+            // no chrono equivalent; leave the parsing to timestamp_str
+            return (full_regex(java), None);
+        }
+        regex.push_str(r);
+        format.push_str(f);
+    }
+    (regex, Some(format))
+}
+
+/// A date regex for patterns chrono can't parse, matching up to the next separator.
+fn full_regex(java: &str) -> String {
+    let words = java.split_whitespace().count().max(1);
+    vec![r"\S+"; words].join(r"\s+")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::PatternLayout;
+    use crate::format::LogFormat;
+
+    #[test]
+    fn parse_with_layout() {
+        let layout = PatternLayout::new("%d{ISO8601} [%t] %-5level %logger{36} - %msg%n").unwrap();
+        let line = "2020-12-21T19:38:28,964 [http-nio-8080-exec-1] WARN  com.example.Service - slow request\njava.lang.IllegalStateException: boom\n\tat com.example.Service.run(Service.java:42)";
+
+        let event = layout.parse(line).unwrap();
+        assert_eq!(event.timestamp, Some(Utc.with_ymd_and_hms(2020, 12, 21, 19, 38, 28).unwrap() + chrono::Duration::milliseconds(964)));
+        assert_eq!(event.thread.as_deref(), Some("http-nio-8080-exec-1"));
+        assert_eq!(event.level.as_deref(), Some("WARN"));
+        assert_eq!(event.logger.as_deref(), Some("com.example.Service"));
+        assert_eq!(event.message, "slow request");
+        assert_eq!(event.exception.unwrap().name, "java.lang.IllegalStateException");
+
+        assert!(layout.parse("not a log line").is_none());
+    }
+}
@@ -0,0 +1,72 @@
+use crate::event::{Exception, Frame, LogEvent};
+use crate::format::{field_value, LogFormat};
+use crate::level;
+use crate::json::{JSONMessage, Thrown};
+
+/// Log4j2 `JSONLayout` events.
+pub struct Log4j2;
+
+impl LogFormat for Log4j2 {
+    fn name(&self) -> &str {
+        "log4j2"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let jm: JSONMessage = serde_json::from_str(line).ok()?;
+        // every field but `level` is optional, so insist on one of the Log4j2 specific ones
+        if jm.loggerName.is_empty() && jm.instant.is_none() && jm.timeMillis.is_none() {
+            return None;
+        }
+        let timestamp = jm.timestamp();
+        let mut fields: Vec<(String, String)> =
+            jm.contextMap.unwrap_or_default().into_iter().collect();
+        fields.sort();
+        // fields outside the known Log4j2 schema (custom KeyValuePairs, additional fields, etc.)
+        let mut extra: Vec<(String, String)> = jm
+            .extra
+            .into_iter()
+            .filter_map(|(k, v)| field_value(v).map(|v| (k, v)))
+            .collect();
+        extra.sort();
+        fields.append(&mut extra);
+        Some(LogEvent {
+            timestamp,
+            level: Some(level::normalize(&jm.level)),
+            logger: Some(jm.loggerName),
+            thread: jm.thread,
+            message: jm.message,
+            fields,
+            exception: jm.thrown.map(Exception::from),
+            ..Default::default()
+        })
+    }
+}
+
+impl From<Thrown> for Exception {
+    fn from(t: Thrown) -> Exception {
+        Exception {
+            name: t.name,
+            message: t.message,
+            frames: t
+                .extendedStackTrace
+                .into_iter()
+                .map(|trace| Frame {
+                    class: trace.class,
+                    method: trace.method,
+                    file: trace.file,
+                    // both are left out of the JSON when unknown, and -1/-2 mark unknown and native lines
+                    line: Some(trace.line).filter(|l| *l > 0),
+                    location: Some(trace.location).filter(|l| !l.is_empty()),
+                })
+                .collect(),
+            common_frames: t.commonElementCount as usize,
+            cause: t.cause.map(|c| Box::new(Exception::from(*c))),
+            suppressed: t
+                .suppressed
+                .unwrap_or_default()
+                .into_iter()
+                .map(Exception::from)
+                .collect(),
+        }
+    }
+}
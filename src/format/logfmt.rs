@@ -0,0 +1,130 @@
+use crate::event::{columns_text, Column, LogEvent, Style};
+use crate::format::{timestamp_value, LogFormat};
+use crate::level;
+
+/// logfmt records (i.e. `time=... level=warn msg="disk low" free=42`) as written by go-kit,
+/// logrus' `TextFormatter`, Heroku and many Go tools.
+pub struct Logfmt;
+
+const TIME_KEYS: [&str; 4] = ["time", "ts", "timestamp", "t"];
+const LEVEL_KEYS: [&str; 4] = ["level", "lvl", "severity", "at"];
+const MESSAGE_KEYS: [&str; 2] = ["msg", "message"];
+const LOGGER_KEYS: [&str; 4] = ["logger", "component", "module", "caller"];
+
+/// Splits a line into its key/value pairs, or `None` when any part of it isn't one.
+pub fn pairs(line: &str) -> Option<Vec<(String, String)>> {
+    let mut pairs = vec![];
+    let mut chars = line.trim().chars().peekable();
+    while chars.peek().is_some() {
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '=' || c.is_whitespace() {
+                break;
+            }
+            if c == '"' {
+                return None;
+            }
+            key.push(c);
+            chars.next();
+        }
+        if key.is_empty() || chars.next() != Some('=') {
+            return None;
+        }
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        'n' => value.push('\n'),
+                        't' => value.push('\t'),
+                        c => value.push(c),
+                    },
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+        }
+        if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+            return None;
+        }
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        pairs.push((key, value));
+    }
+    Some(pairs)
+}
+
+fn take(pairs: &mut Vec<(String, String)>, keys: &[&str]) -> Option<String> {
+    let i = pairs.iter().position(|(k, _)| keys.contains(&k.as_str()))?;
+    Some(pairs.remove(i).1)
+}
+
+impl LogFormat for Logfmt {
+    fn name(&self) -> &str {
+        "logfmt"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        if !line.contains('=') || line.trim_start().starts_with('{') {
+            return None;
+        }
+        let mut pairs = pairs(line)?;
+        let known = |keys: &[&str]| pairs.iter().any(|(k, _)| keys.contains(&k.as_str()));
+        if pairs.len() < 2 || !(known(&LEVEL_KEYS) || known(&MESSAGE_KEYS)) {
+            return None;
+        }
+        let timestamp = take(&mut pairs, &TIME_KEYS).and_then(|t| timestamp_value(&t.into()));
+        let level = take(&mut pairs, &LEVEL_KEYS).map(|l| level::normalize(&l));
+        let logger = take(&mut pairs, &LOGGER_KEYS);
+        let style = match level.as_deref() {
+            Some("ERROR" | "FATAL") => Style::Error,
+            Some("WARN") => Style::Warning,
+            _ => Style::Plain,
+        };
+        let mut columns = vec![];
+        if let Some(message) = take(&mut pairs, &MESSAGE_KEYS) {
+            columns.push(Column::new("message", message, style));
+        }
+        for (k, v) in pairs {
+            let style = if k == "err" || k == "error" { Style::Error } else { Style::Plain };
+            columns.push(Column::keyed(k, v, style));
+        }
+        Some(LogEvent {
+            timestamp,
+            level,
+            logger,
+            message: columns_text(&columns),
+            columns,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pairs, Logfmt};
+    use crate::format::LogFormat;
+
+    #[test]
+    fn parse_logfmt() {
+        let event = Logfmt
+            .parse(r#"time=2024-01-01T00:00:00Z level=warn msg="disk \"data\" low" free=42 path="/var/lib data""#)
+            .unwrap();
+        assert_eq!(event.level.as_deref(), Some("WARN"));
+        assert_eq!(event.attribute("message"), Some(r#"disk "data" low"#));
+        assert_eq!(event.attribute("path"), Some("/var/lib data"));
+        assert_eq!(event.message, r#"disk "data" low free=42 path="/var/lib data""#);
+        assert_eq!(pairs("user=42 logged in"), None);
+        assert!(Logfmt.parse("a=1 b=2").is_none());
+    }
+}
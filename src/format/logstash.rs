@@ -0,0 +1,48 @@
+use crate::event::LogEvent;
+use crate::format::{field_value, json_object, stacktrace, timestamp_str, LogFormat};
+use crate::level;
+
+/// Logback `LogstashEncoder` events from logstash-logback-encoder.
+pub struct Logstash;
+
+impl LogFormat for Logstash {
+    fn name(&self) -> &str {
+        "logstash"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let mut obj = json_object(line)?;
+        if !obj.contains_key("@timestamp") || !obj.get("level")?.is_string() {
+            return None;
+        }
+        let mut take = |key: &str| obj.remove(key).and_then(field_value);
+        let timestamp = take("@timestamp").and_then(|ts| timestamp_str(&ts));
+        let level = take("level").map(|l| level::normalize(&l));
+        let logger = take("logger_name");
+        let thread = take("thread_name");
+        let message = take("message").unwrap_or_default();
+        let stack_trace = take("stack_trace");
+        let exception = stack_trace.as_deref().and_then(stacktrace::parse_java);
+        obj.remove("@version");
+        obj.remove("level_value");
+        // MDC entries and custom fields are flattened into the top level
+        let mut fields: Vec<(String, String)> = obj
+            .into_iter()
+            .filter_map(|(k, v)| field_value(v).map(|v| (k, v)))
+            .collect();
+        // a trace that isn't Java's (or was shortened past recognition) is kept as it is
+        if let (None, Some(st)) = (&exception, stack_trace) {
+            fields.push((String::from("stack_trace"), st));
+        }
+        Some(LogEvent {
+            timestamp,
+            level,
+            logger,
+            thread,
+            message,
+            fields,
+            exception,
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::event::LogEvent;
+use crate::format::{field_value, json_object, stacktrace, timestamp_value, LogFormat};
+use crate::level;
+
+/// A user-defined JSON format, mapping the keys of a bespoke record onto the event's attributes.
+/// Keys are dotted paths into nested objects (i.e. `err.stack`). A line is in the format when it
+/// is a JSON object that has the mapped level and message.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FieldMapping {
+    pub name: String,
+    /// Log groups the mapping is used for, `*` matching anything. Empty means every group.
+    pub groups: Vec<String>,
+    pub timestamp: Option<String>,
+    /// `s`, `ms`, `us` or `ns` for epoch numbers. Guessed from the magnitude when missing.
+    pub timestamp_unit: Option<String>,
+    /// A chrono `strftime` format for timestamp strings. RFC 3339 and its variants when missing.
+    pub timestamp_format: Option<String>,
+    pub level: Option<String>,
+    /// Maps the record's level values (i.e. `"3"` or `"crit"`) onto level names.
+    pub level_aliases: HashMap<String, String>,
+    pub logger: Option<String>,
+    pub thread: Option<String>,
+    pub message: Option<String>,
+    /// A Java, Python or Node.js stack trace.
+    pub exception: Option<String>,
+}
+
+impl FieldMapping {
+    /// Checks the mapping can recognize records and its timestamp unit is known.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.level.is_none() && self.message.is_none() {
+            return Err(format!("Mapping '{}' needs a level or message key", self.name));
+        }
+        match self.timestamp_unit.as_deref() {
+            None | Some("s" | "ms" | "us" | "ns") => Ok(()),
+            Some(unit) => Err(format!("Mapping '{}' has unknown timestamp_unit '{}', expected s, ms, us or ns", self.name, unit)),
+        }
+    }
+
+    fn timestamp_of(&self, v: &Value) -> Option<DateTime<Utc>> {
+        if let Some(unit) = &self.timestamp_unit {
+            let n = match v {
+                Value::String(s) => s.parse::<f64>().ok()?,
+                v => v.as_f64()?,
+            };
+            let nanos = match unit.as_str() {
+                "s" => n * 1e9,
+                "ms" => n * 1e6,
+                "us" => n * 1e3,
+                _ => n,
+            };
+            return Some(DateTime::from_timestamp_nanos(nanos as i64));
+        }
+        if let (Some(format), Some(s)) = (&self.timestamp_format, v.as_str()) {
+            return match DateTime::parse_from_str(s, format) {
+                Ok(ts) => Some(ts.with_timezone(&Utc)),
+                Err(_) => NaiveDateTime::parse_from_str(s, format).ok().map(|ts| ts.and_utc()),
+            };
+        }
+        timestamp_value(v)
+    }
+
+    fn level_of(&self, level: String) -> String {
+        let level = self.level_aliases.get(&level).unwrap_or(&level);
+        level::normalize(level)
+    }
+}
+
+/// Removes the value at a dotted `path`, along with any objects it leaves empty.
+fn take_path(obj: &mut Map<String, Value>, path: &str) -> Option<Value> {
+    let Some((first, rest)) = path.split_once('.') else {
+        return obj.remove(path);
+    };
+    let child = obj.get_mut(first)?.as_object_mut()?;
+    let value = take_path(child, rest);
+    if child.is_empty() {
+        obj.remove(first);
+    }
+    value
+}
+
+fn has_path(obj: &Map<String, Value>, path: &str) -> bool {
+    match path.split_once('.') {
+        Some((first, rest)) => obj.get(first).and_then(Value::as_object).is_some_and(|o| has_path(o, rest)),
+        None => obj.contains_key(path),
+    }
+}
+
+impl LogFormat for FieldMapping {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let mut obj = json_object(line)?;
+        if [&self.level, &self.message].into_iter().flatten().any(|p| !has_path(&obj, p)) {
+            return None;
+        }
+        let mut take = |path: &Option<String>| path.as_deref().and_then(|p| take_path(&mut obj, p));
+        let timestamp = take(&self.timestamp).and_then(|v| self.timestamp_of(&v));
+        let level = take(&self.level).and_then(field_value).map(|l| self.level_of(l));
+        let logger = take(&self.logger).and_then(field_value);
+        let thread = take(&self.thread).and_then(field_value);
+        let message = take(&self.message).and_then(field_value).unwrap_or_default();
+        let trace = take(&self.exception).and_then(field_value);
+        let exception = trace
+            .as_deref()
+            .and_then(|t| stacktrace::parse_java(t).or_else(|| stacktrace::parse_python(t)).or_else(|| stacktrace::parse_node(t)));
+        let mut fields: Vec<(String, String)> = obj
+            .into_iter()
+            .filter_map(|(k, v)| field_value(v).map(|v| (k, v)))
+            .collect();
+        // keep a trace that isn't one we understand
+        if let (None, Some(trace), Some(key)) = (&exception, trace, &self.exception) {
+            fields.push((key.clone(), trace));
+        }
+        Some(LogEvent {
+            timestamp,
+            level,
+            logger,
+            thread,
+            message,
+            fields,
+            exception,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FieldMapping;
+    use crate::format::LogFormat;
+
+    #[test]
+    fn parse_mapped_record() {
+        let mapping: FieldMapping = serde_json::from_str(
+            r#"{"name":"billing","timestamp":"ts_ms","timestamp_unit":"ms","level":"sev","level_aliases":{"3":"err"},"logger":"component","message":"text","exception":"err.stack"}"#,
+        )
+        .unwrap();
+        let event = mapping
+            .parse(r#"{"ts_ms":1608579508964,"sev":3,"component":"invoicer","text":"Failed","err":{"stack":"java.io.IOException: disk full\n\tat com.example.Invoicer.write(Invoicer.java:42)"},"tenant":"acme"}"#)
+            .unwrap();
+        assert_eq!(event.timestamp.unwrap().timestamp_millis(), 1608579508964);
+        assert_eq!(event.level.as_deref(), Some("ERROR"));
+        assert_eq!(event.logger.as_deref(), Some("invoicer"));
+        assert_eq!(event.message, "Failed");
+        assert_eq!(event.exception.unwrap().name, "java.io.IOException");
+        assert_eq!(event.fields, vec![(String::from("tenant"), String::from("acme"))]);
+        assert!(mapping.parse(r#"{"ts_ms":1608579508964,"text":"no level"}"#).is_none());
+    }
+}
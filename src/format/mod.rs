@@ -0,0 +1,213 @@
+//! Parsers that turn a raw log line into a normalized [`LogEvent`].
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::debug;
+use serde_json::{Map, Value};
+
+use crate::event::LogEvent;
+
+pub mod access;
+pub mod cloudtrail;
+pub mod emf;
+pub mod go;
+pub mod lambda;
+pub mod layout;
+pub mod log4j2;
+pub mod logfmt;
+pub mod logstash;
+pub mod mapping;
+pub mod otlp;
+pub mod pino;
+pub mod prefix;
+pub mod python;
+pub mod vpc;
+pub mod stacktrace;
+pub mod syslog;
+
+/// A structured log format.
+pub trait LogFormat: Send + Sync {
+    /// Short name of the format, used in debug output.
+    fn name(&self) -> &str;
+
+    /// Parses a raw line, returning `None` when the line is not in this format.
+    fn parse(&self, line: &str) -> Option<LogEvent>;
+}
+
+/// All built-in formats, most specific first.
+pub fn default_formats() -> Vec<Box<dyn LogFormat>> {
+    vec![
+        Box::new(lambda::LambdaPlatform),
+        Box::new(emf::Emf),
+        Box::new(cloudtrail::CloudTrail),
+        Box::new(access::ApiGateway),
+        Box::new(otlp::Otlp),
+        Box::new(log4j2::Log4j2),
+        Box::new(logstash::Logstash),
+        Box::new(pino::Pino),
+        Box::new(python::Python),
+        Box::new(go::Zap),
+        Box::new(go::Logrus),
+        Box::new(go::Zerolog),
+        Box::new(logfmt::Logfmt),
+        Box::new(vpc::VpcFlowLog),
+        Box::new(access::Alb),
+        Box::new(syslog::Syslog),
+    ]
+}
+
+/// Parses `line` as a JSON object, skipping anything that obviously isn't one.
+pub(crate) fn json_object(line: &str) -> Option<Map<String, Value>> {
+    if !line.trim_start().starts_with('{') {
+        return None;
+    }
+    match serde_json::from_str(line) {
+        Ok(Value::Object(obj)) => Some(obj),
+        _ => None,
+    }
+}
+
+/// Renders a JSON value as a field value; strings are unquoted and nulls dropped.
+pub(crate) fn field_value(v: Value) -> Option<String> {
+    match v {
+        Value::String(s) => Some(s),
+        Value::Null => None,
+        v => Some(v.to_string()),
+    }
+}
+
+/// Parses a JSON timestamp, either an epoch number (seconds, millis, micros or nanos, guessed from
+/// its magnitude) or a date string.
+pub(crate) fn timestamp_value(v: &Value) -> Option<DateTime<Utc>> {
+    match v {
+        Value::Number(n) => epoch_timestamp(n.as_f64()?),
+        Value::String(s) => match s.parse::<f64>() {
+            Ok(n) => epoch_timestamp(n),
+            Err(_) => timestamp_str(s),
+        },
+        _ => None,
+    }
+}
+
+fn epoch_timestamp(n: f64) -> Option<DateTime<Utc>> {
+    let nanos = if n < 1e11 {
+        n * 1e9
+    } else if n < 1e14 {
+        n * 1e6
+    } else if n < 1e17 {
+        n * 1e3
+    } else {
+        n
+    };
+    Some(DateTime::from_timestamp_nanos(nanos as i64))
+}
+
+/// Parses RFC 3339 timestamps and the common variations of it seen in logs: a space instead of
+/// the `T`, a `+0000` offset, a comma before the fraction, or no offset at all (taken as UTC).
+pub(crate) fn timestamp_str(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim().replacen(',', ".", 1);
+    if let Ok(ts) = DateTime::parse_from_rfc3339(&s) {
+        return Some(ts.with_timezone(&Utc));
+    }
+    for fmt in ["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f %z"] {
+        if let Ok(ts) = DateTime::parse_from_str(&s, fmt) {
+            return Some(ts.with_timezone(&Utc));
+        }
+    }
+    for fmt in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(ts) = NaiveDateTime::parse_from_str(&s, fmt) {
+            return Some(ts.and_utc());
+        }
+    }
+    None
+}
+
+/// Picks the format for each log stream. Once a format matches a line from a stream it is tried
+/// first for every later line of that stream; the others are only consulted when it stops matching.
+pub struct Detector {
+    formats: Vec<Box<dyn LogFormat>>,
+    streams: HashMap<String, usize>,
+}
+
+impl Detector {
+    pub fn new(formats: Vec<Box<dyn LogFormat>>) -> Detector {
+        Detector {
+            formats,
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Parses `line` from `stream`, or returns `None` when no format recognizes it. A line that
+    /// isn't recognized as a whole is retried without a runtime [`prefix::Prefix`].
+    pub fn parse(&mut self, stream: &str, line: &str) -> Option<LogEvent> {
+        if let Some(event) = self.parse_line(stream, line) {
+            return Some(event);
+        }
+        let prefix = prefix::Prefix::split(line)?;
+        let mut event = self.parse_line(stream, prefix.payload)?;
+        prefix.apply(&mut event);
+        Some(event)
+    }
+
+    fn parse_line(&mut self, stream: &str, line: &str) -> Option<LogEvent> {
+        let cached = self.streams.get(stream).copied();
+        if let Some(i) = cached {
+            if let Some(event) = self.formats[i].parse(line) {
+                return Some(event);
+            }
+        }
+        for (i, format) in self.formats.iter().enumerate() {
+            if Some(i) == cached {
+                continue;
+            }
+            if let Some(event) = format.parse(line) {
+                debug!("Stream '{}' detected as {}", stream, format.name());
+                self.streams.insert(stream.to_string(), i);
+                return Some(event);
+            }
+        }
+        None
+    }
+}
+
+impl Default for Detector {
+    fn default() -> Detector {
+        Detector::new(default_formats())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Detector, LogFormat};
+    use crate::event::LogEvent;
+
+    struct Prefixed(&'static str);
+
+    impl LogFormat for Prefixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn parse(&self, line: &str) -> Option<LogEvent> {
+            line.strip_prefix(self.0).map(|m| LogEvent {
+                logger: Some(self.0.to_string()),
+                message: m.to_string(),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn detection_is_cached_per_stream() {
+        let mut detector = Detector::new(vec![Box::new(Prefixed("a")), Box::new(Prefixed("ab"))]);
+        assert_eq!(detector.parse("s1", "abc").unwrap().logger.as_deref(), Some("a"));
+        assert_eq!(detector.parse("s2", "xyz"), None);
+
+        let mut detector = Detector::new(vec![Box::new(Prefixed("ab")), Box::new(Prefixed("a"))]);
+        assert_eq!(detector.parse("s1", "ac").unwrap().logger.as_deref(), Some("a"));
+        // "a" is now tried first for s1, but not for other streams
+        assert_eq!(detector.parse("s1", "abc").unwrap().logger.as_deref(), Some("a"));
+        assert_eq!(detector.parse("s2", "abc").unwrap().logger.as_deref(), Some("ab"));
+    }
+}
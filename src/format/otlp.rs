@@ -0,0 +1,184 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+use crate::event::{Exception, LogEvent};
+use crate::format::{json_object, stacktrace, timestamp_value, LogFormat};
+use crate::level;
+
+/// OpenTelemetry log records in the OTLP/JSON encoding, one per line. The record may carry the
+/// `resource` and `scope` it was emitted under; [`split_batch`] turns an exported `resourceLogs`
+/// batch into such lines.
+pub struct Otlp;
+
+/// Maps an OpenTelemetry severity number onto grok's level names.
+pub fn severity_name(number: u64) -> Option<&'static str> {
+    match number {
+        1..=4 => Some("TRACE"),
+        5..=8 => Some("DEBUG"),
+        9..=12 => Some("INFO"),
+        13..=16 => Some("WARN"),
+        17..=20 => Some("ERROR"),
+        21..=24 => Some("FATAL"),
+        _ => None,
+    }
+}
+
+/// A `UnixNano` time, where 0 means unknown.
+fn time(v: &Value) -> Option<DateTime<Utc>> {
+    // nanoseconds are sent as strings since they don't fit in a JSON number
+    match v.as_str().map(str::parse::<i64>) {
+        Some(Ok(0)) => None,
+        Some(Ok(nanos)) => Some(DateTime::from_timestamp_nanos(nanos)),
+        _ if v.as_f64() == Some(0.0) => None,
+        _ => timestamp_value(v),
+    }
+}
+
+/// Renders an `AnyValue`, i.e. `{"stringValue":"x"}` or `{"intValue":"42"}`.
+fn any_value(v: &Value) -> Option<String> {
+    let (kind, value) = v.as_object()?.iter().next()?;
+    match kind.as_str() {
+        "arrayValue" => {
+            let values: Vec<String> = value["values"].as_array()?.iter().filter_map(any_value).collect();
+            Some(format!("[{}]", values.join(", ")))
+        }
+        "kvlistValue" => {
+            let pairs: Vec<String> = key_values(value.get("values")).into_iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            Some(format!("{{{}}}", pairs.join(", ")))
+        }
+        _ => match value {
+            Value::String(s) => Some(s.clone()),
+            Value::Null => None,
+            v => Some(v.to_string()),
+        },
+    }
+}
+
+/// The pairs of a list of `{"key":..,"value":..}` objects.
+fn key_values(list: Option<&Value>) -> Vec<(String, String)> {
+    list.and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|a| Some((a["key"].as_str()?.to_string(), any_value(&a["value"])?)))
+        .collect()
+}
+
+fn take(attributes: &mut Vec<(String, String)>, key: &str) -> Option<String> {
+    let i = attributes.iter().position(|(k, _)| k == key)?;
+    Some(attributes.remove(i).1)
+}
+
+/// The `exception.*` attributes the semantic conventions define, as an exception.
+fn exception(attributes: &mut Vec<(String, String)>) -> Option<Exception> {
+    let name = take(attributes, "exception.type");
+    let message = take(attributes, "exception.message");
+    let trace = take(attributes, "exception.stacktrace");
+    let parsed = trace
+        .as_deref()
+        .and_then(|t| stacktrace::parse_java(t).or_else(|| stacktrace::parse_python(t)).or_else(|| stacktrace::parse_node(t)));
+    match parsed {
+        Some(e) => Some(e),
+        None if name.is_some() || message.is_some() => Some(Exception {
+            name: name.unwrap_or_else(|| String::from("exception")),
+            message,
+            ..Default::default()
+        }),
+        None => None,
+    }
+}
+
+/// Splits an OTLP/JSON export (`{"resourceLogs":[...]}`) into one line per log record, each
+/// carrying its resource and scope. Returns `None` when `line` isn't an export.
+pub fn split_batch(line: &str) -> Option<Vec<String>> {
+    if !line.contains("\"resourceLogs\"") {
+        return None;
+    }
+    let obj = json_object(line)?;
+    let mut records = vec![];
+    for resource_logs in obj.get("resourceLogs")?.as_array()? {
+        let resource = resource_logs.get("resource").cloned().unwrap_or(Value::Null);
+        for scope_logs in resource_logs["scopeLogs"].as_array().into_iter().flatten() {
+            let scope = scope_logs.get("scope").cloned().unwrap_or(Value::Null);
+            for record in scope_logs["logRecords"].as_array().into_iter().flatten() {
+                let mut record: Map<String, Value> = record.as_object()?.clone();
+                record.insert(String::from("resource"), resource.clone());
+                record.insert(String::from("scope"), scope.clone());
+                records.push(Value::Object(record).to_string());
+            }
+        }
+    }
+    Some(records)
+}
+
+impl LogFormat for Otlp {
+    fn name(&self) -> &str {
+        "otlp"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let obj = json_object(line)?;
+        if !obj.contains_key("timeUnixNano") && !obj.contains_key("observedTimeUnixNano") {
+            return None;
+        }
+        if !obj.contains_key("body") && !obj.contains_key("severityNumber") {
+            return None;
+        }
+        let timestamp = obj.get("timeUnixNano").and_then(time).or_else(|| obj.get("observedTimeUnixNano").and_then(time));
+        let level = obj
+            .get("severityNumber")
+            .and_then(Value::as_u64)
+            .and_then(severity_name)
+            .map(String::from)
+            .or_else(|| obj.get("severityText").and_then(Value::as_str).map(level::normalize));
+        let mut fields = key_values(obj.get("attributes"));
+        let exception = exception(&mut fields);
+        let thread = take(&mut fields, "thread.name");
+        let logger = take(&mut fields, "code.namespace").or_else(|| obj.get("scope")?.get("name")?.as_str().map(String::from));
+        let mut resource = key_values(obj.get("resource").and_then(|r| r.get("attributes")));
+        let service = take(&mut resource, "service.name");
+        for key in ["traceId", "spanId"] {
+            if let Some(id) = obj.get(key).and_then(Value::as_str).filter(|id| !id.is_empty()) {
+                fields.push((key.to_string(), id.to_string()));
+            }
+        }
+        Some(LogEvent {
+            timestamp,
+            level,
+            logger,
+            thread,
+            message: obj.get("body").and_then(any_value).unwrap_or_default(),
+            fields,
+            exception,
+            source: service,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_batch, Otlp};
+    use crate::format::LogFormat;
+
+    #[test]
+    fn parse_otlp_batch() {
+        let batch = r#"{"resourceLogs":[{"resource":{"attributes":[{"key":"service.name","value":{"stringValue":"checkout"}}]},"scopeLogs":[{"scope":{"name":"com.example.Checkout"},"logRecords":[{"timeUnixNano":"1704067200000000000","severityNumber":17,"severityText":"Error","body":{"stringValue":"payment failed"},"attributes":[{"key":"order.id","value":{"intValue":"42"}},{"key":"exception.type","value":{"stringValue":"java.io.IOException"}}],"traceId":"5b8efff798038103d269b633813fc60c","spanId":"eee19b7ec3c1b174"}]}]}]}"#;
+        let records = split_batch(batch).unwrap();
+        assert_eq!(records.len(), 1);
+        let event = Otlp.parse(&records[0]).unwrap();
+        assert_eq!(event.timestamp.unwrap().timestamp(), 1704067200);
+        assert_eq!(event.level.as_deref(), Some("ERROR"));
+        assert_eq!(event.logger.as_deref(), Some("com.example.Checkout"));
+        assert_eq!(event.source.as_deref(), Some("checkout"));
+        assert_eq!(event.message, "payment failed");
+        assert_eq!(event.exception.as_ref().unwrap().name, "java.io.IOException");
+        assert_eq!(event.attribute("order.id"), Some("42"));
+        assert_eq!(event.attribute("traceId"), Some("5b8efff798038103d269b633813fc60c"));
+
+        // a record without a time of its own has the time it was observed
+        let record = r#"{"timeUnixNano":"0","observedTimeUnixNano":"1704067201000000000","severityNumber":9,"body":{"stringValue":"started"}}"#;
+        assert_eq!(Otlp.parse(record).unwrap().timestamp.unwrap().timestamp(), 1704067201);
+        let record = r#"{"timeUnixNano":0,"observedTimeUnixNano":"1704067201000000000","severityNumber":9,"body":{"stringValue":"started"}}"#;
+        assert_eq!(Otlp.parse(record).unwrap().timestamp.unwrap().timestamp(), 1704067201);
+    }
+}
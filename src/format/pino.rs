@@ -0,0 +1,81 @@
+use serde_json::Value;
+
+use crate::event::{Exception, LogEvent};
+use crate::format::{field_value, json_object, stacktrace, timestamp_value, LogFormat};
+
+/// Node.js pino and bunyan JSON records.
+pub struct Pino;
+
+/// Maps the numeric pino/bunyan levels onto grok's level names.
+pub fn level_name(level: u64) -> &'static str {
+    match level {
+        0..=10 => "TRACE",
+        11..=20 => "DEBUG",
+        21..=30 => "INFO",
+        31..=40 => "WARN",
+        41..=50 => "ERROR",
+        _ => "FATAL",
+    }
+}
+
+fn exception(err: Value) -> Option<Exception> {
+    let mut err = match err {
+        Value::Object(err) => err,
+        _ => return None,
+    };
+    let name = err.remove("type").and_then(field_value);
+    let message = err.remove("message").and_then(field_value);
+    let mut e = err
+        .remove("stack")
+        .and_then(field_value)
+        .and_then(|stack| stacktrace::parse_node(&stack))
+        .unwrap_or_default();
+    if let Some(name) = name {
+        e.name = name;
+    }
+    if message.is_some() {
+        e.message = message;
+    }
+    if e.name.is_empty() {
+        e.name = String::from("Error");
+    }
+    Some(e)
+}
+
+impl LogFormat for Pino {
+    fn name(&self) -> &str {
+        "pino"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let mut obj = json_object(line)?;
+        let level = obj.get("level")?.as_u64()?;
+        if !obj.contains_key("time") || !(obj.contains_key("msg") || obj.contains_key("err")) {
+            return None;
+        }
+        // pino writes epoch millis, bunyan an ISO-8601 string
+        let timestamp = obj.remove("time").as_ref().and_then(timestamp_value);
+        obj.remove("level");
+        obj.remove("v");
+        let logger = obj.remove("name").and_then(field_value);
+        let exception = obj.remove("err").and_then(exception);
+        let message = match obj.remove("msg").and_then(field_value) {
+            Some(msg) => msg,
+            None => exception.as_ref().and_then(|e| e.message.clone()).unwrap_or_default(),
+        };
+        let fields = obj
+            .into_iter()
+            .filter_map(|(k, v)| field_value(v).map(|v| (k, v)))
+            .collect();
+        Some(LogEvent {
+            timestamp,
+            level: Some(level_name(level).to_string()),
+            logger,
+            thread: None,
+            message,
+            fields,
+            exception,
+            ..Default::default()
+        })
+    }
+}
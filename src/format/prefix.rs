@@ -0,0 +1,108 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+
+use crate::event::LogEvent;
+use crate::format::timestamp_str;
+use crate::level;
+
+fn request_id_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$").unwrap())
+}
+
+/// The text a runtime or sidecar writes in front of a structured line, i.e. the Lambda Node.js
+/// runtime's `2024-01-01T00:00:00.000Z\t<requestId>\tINFO\t{...}`. Only prefixes made up entirely
+/// of a timestamp, a request id and a level are recognized, so JSON quoted in a message is left
+/// alone.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Prefix<'a> {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub request_id: Option<String>,
+    pub level: Option<String>,
+    /// The JSON object following the prefix.
+    pub payload: &'a str,
+}
+
+impl<'a> Prefix<'a> {
+    pub fn split(line: &'a str) -> Option<Prefix<'a>> {
+        let start = line.find('{')?;
+        let (head, payload) = line.split_at(start);
+        let tokens: Vec<&str> = head.split_whitespace().collect();
+        if tokens.is_empty() {
+            return None;
+        }
+        let mut prefix = Prefix {
+            payload,
+            ..Default::default()
+        };
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = tokens[i];
+            let level = token.trim_start_matches('[').trim_end_matches(']');
+            if prefix.timestamp.is_none() {
+                // a date and time separated by a space span two tokens
+                if let Some(ts) = tokens.get(i + 1).and_then(|next| timestamp_str(&format!("{} {}", token, next))) {
+                    prefix.timestamp = Some(ts);
+                    i += 2;
+                    continue;
+                }
+                if let Some(ts) = timestamp_str(token) {
+                    prefix.timestamp = Some(ts);
+                    i += 1;
+                    continue;
+                }
+            }
+            if prefix.request_id.is_none() && request_id_re().is_match(token) {
+                prefix.request_id = Some(token.to_string());
+            } else if prefix.level.is_none() && level::rank(&level::normalize(level)).is_some() {
+                prefix.level = Some(level::normalize(level));
+            } else {
+                return None;
+            }
+            i += 1;
+        }
+        Some(prefix)
+    }
+
+    /// Adds the prefix to an event parsed from the payload. The prefix's timestamp and level are
+    /// only used when the payload has none; the request id and runtime level are kept as fields.
+    pub fn apply(&self, event: &mut LogEvent) {
+        if event.timestamp.is_none() {
+            event.timestamp = self.timestamp;
+        }
+        if let Some(id) = &self.request_id {
+            event.fields.push((String::from("requestId"), id.clone()));
+        }
+        if let Some(level) = &self.level {
+            if event.level.is_none() {
+                event.level = Some(level.clone());
+            }
+            event.fields.push((String::from("runtimeLevel"), level.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Prefix;
+    use crate::format::Detector;
+
+    #[test]
+    fn parse_prefixed_json() {
+        let line = "2024-01-01T00:00:00.000Z\t8f5b7b6e-1d3c-4c1e-9b1e-0c2b8d1c2f3a\tINFO\t{\"level\":30,\"time\":1704067200000,\"msg\":\"hello\"}";
+        let event = Detector::default().parse("s", line).unwrap();
+        assert_eq!(event.message, "hello");
+        assert_eq!(
+            event.fields,
+            vec![
+                (String::from("requestId"), String::from("8f5b7b6e-1d3c-4c1e-9b1e-0c2b8d1c2f3a")),
+                (String::from("runtimeLevel"), String::from("INFO")),
+            ]
+        );
+        assert_eq!(Prefix::split("[ERROR] 2024-01-01 00:00:00,123 {\"a\":1}").unwrap().level.as_deref(), Some("ERROR"));
+        assert_eq!(Prefix::split("Sending {\"a\":1}"), None);
+        assert_eq!(Prefix::split("{\"a\":1}"), None);
+    }
+}
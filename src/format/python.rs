@@ -0,0 +1,67 @@
+use serde_json::{Map, Value};
+
+use crate::event::LogEvent;
+use crate::format::{field_value, json_object, stacktrace, timestamp_value, LogFormat};
+use crate::level;
+
+/// python-json-logger and structlog JSON records.
+pub struct Python;
+
+fn take_first(obj: &mut Map<String, Value>, keys: &[&str]) -> Option<Value> {
+    let mut found = None;
+    for key in keys {
+        if let Some(v) = obj.remove(*key) {
+            if found.is_none() {
+                found = Some(v);
+            }
+        }
+    }
+    found
+}
+
+impl LogFormat for Python {
+    fn name(&self) -> &str {
+        "python"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let mut obj = json_object(line)?;
+        let structlog = obj.contains_key("event") && obj.get("level").is_some_and(Value::is_string);
+        if !obj.contains_key("levelname") && !structlog {
+            return None;
+        }
+        let timestamp = take_first(&mut obj, &["asctime", "timestamp", "created"])
+            .as_ref()
+            .and_then(timestamp_value);
+        let level = take_first(&mut obj, &["levelname", "level"])
+            .and_then(field_value)
+            .map(|l| level::normalize(&l));
+        let logger = take_first(&mut obj, &["name", "logger"]).and_then(field_value);
+        let thread = obj.remove("threadName").and_then(field_value);
+        let message = take_first(&mut obj, &["message", "event", "msg"])
+            .and_then(field_value)
+            .unwrap_or_default();
+        const TRACEBACK_KEYS: [&str; 3] = ["exc_info", "exception", "exc_text"];
+        let traceback_key = TRACEBACK_KEYS.into_iter().find(|k| obj.contains_key(*k));
+        let traceback = take_first(&mut obj, &TRACEBACK_KEYS).and_then(field_value);
+        let exception = traceback.as_deref().and_then(stacktrace::parse_python);
+        let mut fields: Vec<(String, String)> = obj
+            .into_iter()
+            .filter_map(|(k, v)| field_value(v).map(|v| (k, v)))
+            .collect();
+        // structlog's dict tracebacks and anything else that isn't a formatted traceback are kept as is
+        if let (None, Some(key), Some(tb)) = (&exception, traceback_key, traceback) {
+            fields.push((String::from(key), tb));
+        }
+        Some(LogEvent {
+            timestamp,
+            level,
+            logger,
+            thread,
+            message,
+            fields,
+            exception,
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,425 @@
+//! Parsing of stack traces that arrive as plain text rather than structured frames.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::event::{Exception, Frame};
+
+enum Token<'a> {
+    Frame(usize, Frame),
+    More(usize, usize),
+    Caused(usize, &'a str),
+    Suppressed(usize, &'a str),
+    Other(&'a str),
+}
+
+fn frame_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^at (?P<symbol>[^(\s]+)\((?P<source>[^)]*)\)(?:\s+~?\[(?P<location>[^\]]*)\])?").unwrap()
+    })
+}
+
+fn more_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\.\.\. (\d+) (?:more|common frames omitted)").unwrap())
+}
+
+/// Tabs count as one level, spaces as a quarter level each (rounded up).
+fn indent_of(line: &str) -> usize {
+    let width: usize = line
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+    width.div_ceil(4)
+}
+
+fn tokenize(line: &str) -> Token<'_> {
+    let indent = indent_of(line);
+    let text = line.trim();
+    if let Some(caps) = frame_re().captures(text) {
+        let mut frame = java_frame(&caps["symbol"], &caps["source"]);
+        if let Some(location) = caps.name("location") {
+            frame.location = Some(location.as_str().to_string());
+        }
+        return Token::Frame(indent, frame);
+    }
+    if let Some(caps) = more_re().captures(text) {
+        return Token::More(indent, caps[1].parse().unwrap_or(0));
+    }
+    if let Some(header) = text.strip_prefix("Caused by: ") {
+        return Token::Caused(indent, header);
+    }
+    if let Some(header) = text.strip_prefix("Suppressed: ") {
+        return Token::Suppressed(indent, header);
+    }
+    Token::Other(line)
+}
+
+fn java_frame(symbol: &str, source: &str) -> Frame {
+    let mut frame = Frame::default();
+    // strip a JPMS module or class loader prefix (java.base/java.lang.Thread.run), but not the
+    // slash in hidden lambda class names (Foo$$Lambda$14/0x0000000800066840.run)
+    let symbol = match symbol.rsplit_once('/') {
+        Some((module, rest)) if !module.contains('$') && !rest.starts_with("0x") => {
+            frame.location = Some(module.trim_end_matches('/').to_string());
+            rest
+        }
+        _ => symbol,
+    };
+    match symbol.rsplit_once('.') {
+        Some((class, method)) => {
+            frame.class = class.to_string();
+            frame.method = method.to_string();
+        }
+        None => frame.method = symbol.to_string(),
+    }
+    match source.rsplit_once(':') {
+        Some((file, line)) if line.parse::<i32>().is_ok() => {
+            frame.file = Some(file.to_string());
+            frame.line = line.parse().ok();
+        }
+        _ => frame.file = Some(source.to_string()),
+    }
+    frame
+}
+
+fn header(text: &str) -> Exception {
+    match text.split_once(": ") {
+        Some((name, message)) => Exception {
+            name: name.trim().to_string(),
+            message: Some(message.to_string()),
+            ..Default::default()
+        },
+        None => Exception {
+            name: text.trim().trim_end_matches(':').to_string(),
+            ..Default::default()
+        },
+    }
+}
+
+/// Parses the exception starting at `tokens[*pos]`, whose header sits at `indent`.
+fn parse_exception(tokens: &[Token], pos: &mut usize, indent: usize, mut e: Exception) -> Exception {
+    let mut omitted = 0;
+    while *pos < tokens.len() {
+        match &tokens[*pos] {
+            Token::Frame(i, frame) if *i > indent => e.frames.push(frame.clone()),
+            Token::More(i, n) if *i > indent => omitted = *n,
+            Token::Suppressed(i, text) if *i == indent + 1 => {
+                *pos += 1;
+                let s = parse_exception(tokens, pos, *i, header(text));
+                e.suppressed.push(s);
+                continue;
+            }
+            Token::Caused(i, text) if *i == indent => {
+                *pos += 1;
+                let c = parse_exception(tokens, pos, *i, header(text));
+                e.cause = Some(Box::new(c));
+                break;
+            }
+            Token::Other(text) if e.frames.is_empty() && !text.trim().is_empty() => {
+                // multi-line exception messages
+                let message = e.message.get_or_insert_with(String::new);
+                message.push('\n');
+                message.push_str(text);
+            }
+            _ => break,
+        }
+        *pos += 1;
+    }
+    e.common_frames = omitted;
+    e
+}
+
+/// Text traces drop the frames shared with the enclosing trace ("... N more"). Copy them back from
+/// the enclosing exception so the result matches what Log4j2 reports as `commonElementCount`.
+fn restore_common_frames(e: &mut Exception, enclosing: Option<&[Frame]>) {
+    let omitted = e.common_frames;
+    if let Some(enclosing) = enclosing {
+        let shared = omitted.min(enclosing.len());
+        e.frames.extend_from_slice(&enclosing[enclosing.len() - shared..]);
+        e.common_frames = shared;
+    } else {
+        e.common_frames = 0;
+    }
+    let frames = e.frames.clone();
+    for s in e.suppressed.iter_mut() {
+        restore_common_frames(s, Some(&frames));
+    }
+    if let Some(c) = e.cause.as_mut() {
+        restore_common_frames(c, Some(&frames));
+    }
+}
+
+/// Parses a Java `printStackTrace` style trace, including Logback's "common frames omitted" and
+/// `~[jar:version]` packaging data. Returns `None` when `text` has no frames at all.
+pub fn parse_java(text: &str) -> Option<Exception> {
+    let mut lines = text.lines().skip_while(|l| l.trim().is_empty());
+    let first = lines.next()?;
+    let tokens: Vec<Token> = lines.map(tokenize).collect();
+    if !tokens.iter().any(|t| matches!(t, Token::Frame(..))) {
+        return None;
+    }
+    let mut pos = 0;
+    let mut e = parse_exception(&tokens, &mut pos, indent_of(first), header(first.trim()));
+    restore_common_frames(&mut e, None);
+    Some(e)
+}
+
+fn node_frame_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^at (?:(?P<function>.+?) \()?(?P<file>[^()]+?)(?::(?P<line>\d+))?(?::\d+)?\)?$").unwrap()
+    })
+}
+
+/// Parses a V8 `Error.stack` string. `caused by:` sections, as appended by pino's error
+/// serializer, become the cause chain.
+pub fn parse_node(text: &str) -> Option<Exception> {
+    let mut root: Option<Exception> = None;
+    let mut chain: Vec<Exception> = vec![];
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(caps) = node_frame_re().captures(trimmed) {
+            if let Some(e) = chain.last_mut() {
+                e.frames.push(Frame {
+                    method: caps.name("function").map_or("<anonymous>", |f| f.as_str()).to_string(),
+                    file: Some(caps["file"].to_string()),
+                    line: caps.name("line").and_then(|l| l.as_str().parse().ok()),
+                    ..Default::default()
+                });
+            }
+        } else if let Some(cause) = trimmed.strip_prefix("caused by: ") {
+            chain.push(header(cause));
+        } else if chain.is_empty() {
+            chain.push(header(trimmed));
+        } else if let Some(e) = chain.last_mut() {
+            if e.frames.is_empty() && !trimmed.is_empty() {
+                let message = e.message.get_or_insert_with(String::new);
+                message.push('\n');
+                message.push_str(line);
+            }
+        }
+    }
+    while let Some(mut e) = chain.pop() {
+        e.cause = root.map(Box::new);
+        root = Some(e);
+    }
+    root
+}
+
+/// Parses a Go `runtime/debug.Stack` style trace, as written to zap's `stacktrace` field: each
+/// frame is a function line followed by an indented `file:line +0xoffset` line.
+pub fn parse_go(text: &str) -> Vec<Frame> {
+    let mut frames: Vec<Frame> = vec![];
+    for line in text.lines() {
+        if line.trim().is_empty() || line.starts_with("goroutine ") {
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            if let Some(frame) = frames.last_mut() {
+                let location = line.trim().split(" +0x").next().unwrap_or_default();
+                match location.rsplit_once(':') {
+                    Some((file, line)) => {
+                        frame.file = Some(file.to_string());
+                        frame.line = line.parse().ok();
+                    }
+                    None => frame.file = Some(location.to_string()),
+                }
+            }
+        } else {
+            frames.push(go_frame(line.trim()));
+        }
+    }
+    frames
+}
+
+/// Splits a Go function name such as `github.com/acme/svc.(*Server).Handle(...)` into the package
+/// (and receiver) part and the function.
+pub fn go_frame(function: &str) -> Frame {
+    // drop a trailing argument list; a receiver such as (*Server) is always followed by a name
+    let mut function = function;
+    if function.ends_with(')') {
+        let mut depth = 0;
+        for (i, c) in function.char_indices().rev() {
+            match c {
+                ')' => depth += 1,
+                '(' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                function = &function[..i];
+                break;
+            }
+        }
+    }
+    // the last path element holds the package name; split after it
+    let base = function.rfind('/').map_or(0, |i| i + 1);
+    match function[base..].rfind('.') {
+        Some(i) => Frame {
+            class: function[..base + i].to_string(),
+            method: function[base + i + 1..].to_string(),
+            ..Default::default()
+        },
+        None => Frame {
+            method: function.to_string(),
+            ..Default::default()
+        },
+    }
+}
+
+fn python_frame_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"^File "(?P<file>[^"]+)", line (?P<line>\d+)(?:, in (?P<function>.+))?$"#).unwrap())
+}
+
+/// Parses a Python traceback. Frames keep Python's most-recent-call-last order and carry the
+/// source line as their location. Chained tracebacks ("During handling of the above exception" or
+/// "The above exception was the direct cause") become the cause chain of the last exception.
+pub fn parse_python(text: &str) -> Option<Exception> {
+    let mut chain: Vec<Exception> = vec![];
+    let mut current = Exception::default();
+    let mut in_traceback = false;
+    let mut in_message = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("Traceback (most recent call last)") {
+            current = Exception::default();
+            in_traceback = true;
+            in_message = false;
+            continue;
+        }
+        if !in_traceback {
+            continue;
+        }
+        if let Some(caps) = python_frame_re().captures(trimmed) {
+            current.frames.push(Frame {
+                method: caps.name("function").map_or("<module>", |f| f.as_str()).to_string(),
+                file: Some(caps["file"].to_string()),
+                line: caps["line"].parse().ok(),
+                ..Default::default()
+            });
+        } else if line.starts_with(char::is_whitespace) && !in_message {
+            // the source line of the previous frame; skip 3.11+ caret markers
+            if let Some(frame) = current.frames.last_mut() {
+                if frame.location.is_none() && !trimmed.is_empty() {
+                    frame.location = Some(trimmed.to_string());
+                }
+            }
+        } else if trimmed.starts_with("During handling of the above exception")
+            || trimmed.starts_with("The above exception was the direct cause")
+        {
+            in_traceback = false;
+        } else if in_message {
+            if trimmed.is_empty() {
+                in_message = false;
+            } else if let Some(e) = chain.last_mut() {
+                let message = e.message.get_or_insert_with(String::new);
+                message.push('\n');
+                message.push_str(line);
+            }
+        } else if !trimmed.is_empty() {
+            let mut e = header(trimmed);
+            e.frames = std::mem::take(&mut current.frames);
+            chain.push(e);
+            in_message = true;
+        }
+    }
+    let mut root: Option<Exception> = None;
+    for mut e in chain {
+        e.cause = root.map(Box::new);
+        root = Some(e);
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_go, parse_java, parse_node, parse_python};
+
+    #[test]
+    fn parse_java_trace() {
+        let trace = "org.springframework.dao.DataAccessResourceFailureException: could not save\n\
+            \tat com.example.Dao.save(Dao.java:42) ~[app.jar:1.0]\n\
+            \tat java.base/java.lang.Thread.run(Thread.java:829)\n\
+            \tSuppressed: java.io.IOException: close failed\n\
+            \t\tat com.example.Dao.close(Dao.java:50)\n\
+            \t\t... 1 more\n\
+            Caused by: java.net.SocketException: Connection reset\n\
+            \tat java.net.SocketInputStream.read(Native Method)\n\
+            \t... 2 common frames omitted\n";
+
+        let e = parse_java(trace).unwrap();
+        assert_eq!(e.name, "org.springframework.dao.DataAccessResourceFailureException");
+        assert_eq!(e.message.as_deref(), Some("could not save"));
+        assert_eq!(e.frames.len(), 2);
+        assert_eq!(e.frames[0].class, "com.example.Dao");
+        assert_eq!(e.frames[0].line, Some(42));
+        assert_eq!(e.frames[0].location.as_deref(), Some("app.jar:1.0"));
+        assert_eq!(e.frames[1].class, "java.lang.Thread");
+        assert_eq!(e.frames[1].location.as_deref(), Some("java.base"));
+
+        let s = &e.suppressed[0];
+        assert_eq!(s.name, "java.io.IOException");
+        assert_eq!(s.frames.len(), 2);
+        assert_eq!(s.common_frames, 1);
+
+        let c = e.cause.unwrap();
+        assert_eq!(c.name, "java.net.SocketException");
+        assert_eq!(c.frames[0].file.as_deref(), Some("Native Method"));
+        assert_eq!(c.frames[0].line, None);
+        assert_eq!(c.frames.len(), 3);
+        assert_eq!(c.common_frames, 2);
+    }
+
+    #[test]
+    fn parse_node_stack() {
+        let stack = "TypeError: Cannot read properties of undefined (reading 'id')\n    at handler (/var/task/index.js:10:15)\n    at /var/task/index.js:5:3\n    at async Promise.all (index 0)\ncaused by: Error: lookup failed\n    at lookup (/var/task/db.js:3:9)";
+
+        let e = parse_node(stack).unwrap();
+        assert_eq!(e.name, "TypeError");
+        assert_eq!(e.message.as_deref(), Some("Cannot read properties of undefined (reading 'id')"));
+        assert_eq!(e.frames.len(), 3);
+        assert_eq!(e.frames[0].method, "handler");
+        assert_eq!(e.frames[0].file.as_deref(), Some("/var/task/index.js"));
+        assert_eq!(e.frames[0].line, Some(10));
+        assert_eq!(e.frames[1].method, "<anonymous>");
+        assert_eq!(e.frames[2].method, "async Promise.all");
+        assert_eq!(e.cause.unwrap().frames[0].method, "lookup");
+    }
+
+    #[test]
+    fn parse_go_stack() {
+        let stack = "github.com/acme/svc/api.(*Server).Handle\n\t/app/api/server.go:42\nmain.main.func1(0xc000010000)\n\t/app/main.go:10 +0x1d";
+
+        let frames = parse_go(stack);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].class, "github.com/acme/svc/api.(*Server)");
+        assert_eq!(frames[0].method, "Handle");
+        assert_eq!(frames[0].file.as_deref(), Some("/app/api/server.go"));
+        assert_eq!(frames[0].line, Some(42));
+        assert_eq!(frames[1].class, "main.main");
+        assert_eq!(frames[1].method, "func1");
+        assert_eq!(frames[1].line, Some(10));
+    }
+
+    #[test]
+    fn parse_python_traceback() {
+        let traceback = "Traceback (most recent call last):\n  File \"/app/db.py\", line 3, in lookup\n    return cache[key]\nKeyError: 'id'\n\nDuring handling of the above exception, another exception occurred:\n\nTraceback (most recent call last):\n  File \"/app/handler.py\", line 10, in handle\n    result = lookup(key)\n             ^^^^^^^^^^^\nValueError: lookup failed\n";
+
+        let e = parse_python(traceback).unwrap();
+        assert_eq!(e.name, "ValueError");
+        assert_eq!(e.message.as_deref(), Some("lookup failed"));
+        assert_eq!(e.frames.len(), 1);
+        assert_eq!(e.frames[0].method, "handle");
+        assert_eq!(e.frames[0].file.as_deref(), Some("/app/handler.py"));
+        assert_eq!(e.frames[0].line, Some(10));
+        assert_eq!(e.frames[0].location.as_deref(), Some("result = lookup(key)"));
+        let c = e.cause.unwrap();
+        assert_eq!(c.name, "KeyError");
+        assert_eq!(c.message.as_deref(), Some("'id'"));
+        assert_eq!(c.frames[0].method, "lookup");
+    }
+}
@@ -0,0 +1,168 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, Utc};
+use regex::Regex;
+
+use crate::event::LogEvent;
+use crate::format::{timestamp_str, LogFormat};
+
+/// RFC 5424 and RFC 3164 (BSD) syslog lines, with or without the `<PRI>` that files written by
+/// syslog daemons leave out.
+pub struct Syslog;
+
+const FACILITIES: [&str; 24] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron", "authpriv", "ftp", "ntp", "security",
+    "console", "solaris-cron", "local0", "local1", "local2", "local3", "local4", "local5", "local6", "local7",
+];
+
+fn rfc5424_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^<(\d{1,3})>1 (\S+) (\S+) (\S+) (\S+) (\S+) (.*)$").unwrap())
+}
+
+fn rfc3164_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^(?:<(\d{1,3})>)?([A-Z][a-z]{2} [ \d]\d \d\d:\d\d:\d\d|\d{4}-\d\d-\d\dT\S+) (\S+) ([^\s:\[]+)(?:\[([^\]]*)\])?: ?(.*)$").unwrap()
+    })
+}
+
+/// Maps a syslog severity onto grok's level names.
+pub fn severity_name(severity: u8) -> &'static str {
+    match severity {
+        0..=2 => "FATAL",
+        3 => "ERROR",
+        4 => "WARN",
+        5 | 6 => "INFO",
+        _ => "DEBUG",
+    }
+}
+
+/// The level and the facility field of a `<PRI>` value.
+fn priority(pri: Option<&str>) -> (Option<String>, Vec<(String, String)>) {
+    let Some(pri) = pri.and_then(|p| p.parse::<u8>().ok()) else {
+        return (None, vec![]);
+    };
+    let facility = FACILITIES.get(usize::from(pri / 8)).copied().unwrap_or("unknown");
+    (Some(severity_name(pri % 8).to_string()), vec![(String::from("facility"), facility.to_string())])
+}
+
+fn nil(value: &str) -> Option<String> {
+    (value != "-").then(|| value.to_string())
+}
+
+/// Splits RFC 5424 structured data from the message, turning every `[id key="value"]` parameter
+/// into an `id.key` field.
+fn structured_data(text: &str) -> Option<(Vec<(String, String)>, &str)> {
+    if let Some(rest) = text.strip_prefix('-') {
+        return Some((vec![], rest));
+    }
+    let mut fields = vec![];
+    let mut rest = text;
+    while let Some(element) = rest.strip_prefix('[') {
+        let id_end = element.find([' ', ']'])?;
+        let id = &element[..id_end];
+        let mut params = &element[id_end..];
+        loop {
+            params = params.trim_start_matches(' ');
+            if let Some(after) = params.strip_prefix(']') {
+                rest = after;
+                break;
+            }
+            let (name, value) = params.split_once("=\"")?;
+            let mut unescaped = String::new();
+            let mut chars = value.char_indices();
+            let end = loop {
+                match chars.next()? {
+                    (i, '"') => break i,
+                    (_, '\\') => unescaped.push(chars.next()?.1),
+                    (_, c) => unescaped.push(c),
+                }
+            };
+            fields.push((format!("{}.{}", id, name), unescaped));
+            params = &value[end + 1..];
+        }
+    }
+    Some((fields, rest))
+}
+
+/// BSD timestamps have no year; they are taken to be in the last year.
+fn bsd_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    if let Some(ts) = timestamp_str(s) {
+        return Some(ts);
+    }
+    let now = Utc::now();
+    let ts = NaiveDateTime::parse_from_str(&format!("{} {}", now.year(), s), "%Y %b %e %H:%M:%S").ok()?.and_utc();
+    if ts > now + Duration::days(1) {
+        return ts.with_year(now.year() - 1);
+    }
+    Some(ts)
+}
+
+impl LogFormat for Syslog {
+    fn name(&self) -> &str {
+        "syslog"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let line = line.trim_end();
+        if let Some(c) = rfc5424_re().captures(line) {
+            let (level, mut fields) = priority(c.get(1).map(|m| m.as_str()));
+            fields.extend(nil(&c[3]).map(|h| (String::from("host"), h)));
+            fields.extend(nil(&c[6]).map(|m| (String::from("msgid"), m)));
+            let (data, message) = structured_data(&c[7])?;
+            fields.extend(data);
+            return Some(LogEvent {
+                timestamp: timestamp_str(&c[2]),
+                level,
+                logger: nil(&c[4]),
+                thread: nil(&c[5]),
+                message: message.trim_start().trim_start_matches('\u{feff}').to_string(),
+                fields,
+                ..Default::default()
+            });
+        }
+        let c = rfc3164_re().captures(line)?;
+        let (level, mut fields) = priority(c.get(1).map(|m| m.as_str()));
+        fields.push((String::from("host"), c[3].to_string()));
+        Some(LogEvent {
+            timestamp: bsd_timestamp(&c[2]),
+            level,
+            logger: Some(c[4].to_string()),
+            thread: c.get(5).map(|m| m.as_str().to_string()),
+            message: c[6].to_string(),
+            fields,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Syslog;
+    use crate::format::LogFormat;
+
+    #[test]
+    fn parse_syslog() {
+        let event = Syslog
+            .parse(r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="Application \"x\""] An application event"#)
+            .unwrap();
+        assert_eq!(event.level.as_deref(), Some("INFO"));
+        assert_eq!(event.logger.as_deref(), Some("evntslog"));
+        assert_eq!(event.thread, None);
+        assert_eq!(event.message, "An application event");
+        assert_eq!(event.attribute("facility"), Some("local4"));
+        assert_eq!(event.attribute("exampleSDID@32473.eventSource"), Some(r#"Application "x""#));
+
+        let event = Syslog.parse("<34>Oct 11 22:14:15 mymachine su[123]: 'su root' failed for lonvick on /dev/pts/8").unwrap();
+        assert_eq!(event.level.as_deref(), Some("FATAL"));
+        assert_eq!(event.thread.as_deref(), Some("123"));
+        assert_eq!(event.attribute("host"), Some("mymachine"));
+        assert_eq!(event.message, "'su root' failed for lonvick on /dev/pts/8");
+
+        let event = Syslog.parse("Jan  1 00:00:01 web1 CRON[42]: (root) CMD (run-parts /etc/cron.hourly)").unwrap();
+        assert_eq!(event.level, None);
+        assert_eq!(event.logger.as_deref(), Some("CRON"));
+        assert!(event.timestamp.is_some());
+    }
+}
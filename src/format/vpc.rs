@@ -0,0 +1,126 @@
+use chrono::DateTime;
+
+use crate::event::{columns_text, Column, LogEvent, Style};
+use crate::format::LogFormat;
+
+/// Field names of VPC Flow Log records: the 14 version 2 (default format) fields followed by the
+/// version 3 to 5 fields in the order AWS documents them.
+const FIELDS: [&str; 29] = [
+    "version",
+    "account-id",
+    "interface-id",
+    "srcaddr",
+    "dstaddr",
+    "srcport",
+    "dstport",
+    "protocol",
+    "packets",
+    "bytes",
+    "start",
+    "end",
+    "action",
+    "log-status",
+    "vpc-id",
+    "subnet-id",
+    "instance-id",
+    "tcp-flags",
+    "type",
+    "pkt-srcaddr",
+    "pkt-dstaddr",
+    "region",
+    "az-id",
+    "sublocation-type",
+    "sublocation-id",
+    "pkt-src-aws-service",
+    "pkt-dst-aws-service",
+    "flow-direction",
+    "traffic-path",
+];
+
+/// VPC Flow Log records, in the default format or with the version 3 to 5 fields appended.
+pub struct VpcFlowLog;
+
+fn protocol_name(protocol: &str) -> &str {
+    match protocol {
+        "1" => "ICMP",
+        "6" => "TCP",
+        "17" => "UDP",
+        "58" => "ICMPv6",
+        p => p,
+    }
+}
+
+impl LogFormat for VpcFlowLog {
+    fn name(&self) -> &str {
+        "vpc-flow-log"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let values: Vec<&str> = line.split_whitespace().collect();
+        if values.len() < 14 || values.len() > FIELDS.len() || !matches!(values[0], "2" | "3" | "4" | "5") {
+            return None;
+        }
+        let action = values[12];
+        let status = values[13];
+        if !matches!(action, "ACCEPT" | "REJECT" | "-") || !matches!(status, "OK" | "NODATA" | "SKIPDATA") {
+            return None;
+        }
+        let (level, style) = match (action, status) {
+            ("REJECT", _) => ("WARN", Style::Error),
+            ("ACCEPT", _) => ("INFO", Style::Good),
+            _ => ("DEBUG", Style::Plain),
+        };
+        let columns = if status == "OK" {
+            vec![
+                Column::new("action", action, style),
+                Column::new("protocol", protocol_name(values[7]), Style::Plain),
+                Column::new("src", format!("{}:{}", values[3], values[5]), Style::Highlight),
+                Column::new("", "->", Style::Plain),
+                Column::new("dst", format!("{}:{}", values[4], values[6]), Style::Highlight),
+                Column::new("traffic", format!("{} packets {} bytes", values[8], values[9]), Style::Plain),
+            ]
+        } else {
+            vec![Column::new("log-status", status, Style::Plain)]
+        };
+        let fields = FIELDS
+            .iter()
+            .zip(values.iter())
+            .skip(14)
+            .filter(|(_, v)| **v != "-")
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .chain(std::iter::once((String::from("account-id"), values[1].to_string())))
+            .collect();
+        Some(LogEvent {
+            timestamp: values[10].parse().ok().and_then(|s| DateTime::from_timestamp(s, 0)),
+            level: Some(level.to_string()),
+            logger: Some(values[2].to_string()),
+            message: columns_text(&columns),
+            fields,
+            columns,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VpcFlowLog;
+    use crate::format::LogFormat;
+
+    #[test]
+    fn parse_flow_log() {
+        let event = VpcFlowLog
+            .parse("2 123456789010 eni-1235b8ca123456789 172.31.9.69 172.31.9.12 49761 3389 6 20 4249 1418530010 1418530070 REJECT OK")
+            .unwrap();
+        assert_eq!(event.level.as_deref(), Some("WARN"));
+        assert_eq!(event.logger.as_deref(), Some("eni-1235b8ca123456789"));
+        assert_eq!(event.message, "REJECT TCP 172.31.9.69:49761 -> 172.31.9.12:3389 20 packets 4249 bytes");
+
+        let event = VpcFlowLog
+            .parse("3 123456789010 eni-1235b8ca123456789 - - - - - - - 1431280876 1431280934 - NODATA vpc-abcdefab012345678 - - - - - -")
+            .unwrap();
+        assert_eq!(event.level.as_deref(), Some("DEBUG"));
+        assert!(event.fields.contains(&("vpc-id".to_string(), "vpc-abcdefab012345678".to_string())));
+        assert!(VpcFlowLog.parse("2 INFO this is not a flow log").is_none());
+    }
+}
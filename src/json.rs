@@ -1,51 +1,56 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use std::collections::HashMap;
 
+/// A Log4j2 `JSONLayout`/`JsonTemplateLayout` event. Only `level` is required so that layouts
+/// configured without thread info, stack traces or instants still parse; any field not modelled
+/// here is kept in `extra`.
 #[derive(Serialize, Deserialize)]
 #[allow(non_snake_case)]
 pub struct JSONMessage {
-    pub timeMillis: Option<u64>,
-    pub thread: String,
+    pub timeMillis: Option<i64>,
+    pub thread: Option<String>,
     pub level: String,
+    #[serde(default)]
     pub loggerName: String,
+    #[serde(default)]
     pub message: String,
     pub thrown: Option<Thrown>,
-    pub threadId: i32,
-    pub threadPriority: u32,
-    pub endOfBatch: bool,
-    pub loggerFqcn: String,
+    pub threadId: Option<i64>,
+    pub threadPriority: Option<i32>,
+    pub endOfBatch: Option<bool>,
+    pub loggerFqcn: Option<String>,
     pub instant: Option<Instant>,
     pub contextMap: Option<HashMap<String, String>>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
-#[derive(Serialize, Deserialize)]
-#[allow(non_snake_case)]
-pub struct Thrown {
-    pub commonElementCount: u32,
-    pub name: String,
-    pub message: Option<String>,
-    pub extendedStackTrace: Vec<Trace>,
-    pub cause: Option<Cause>
-}
-
-#[derive(Serialize, Deserialize)]
-#[allow(non_snake_case)]
-pub struct Cause {
-    pub commonElementCount: u32,
-    pub name: String,
-    pub message: String,
-    pub extendedStackTrace: Vec<Trace>,
-    pub cause: Option<SubCause>,
+impl JSONMessage {
+    /// The event time, taken from `instant` when present and `timeMillis` otherwise.
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        match (&self.instant, self.timeMillis) {
+            (Some(i), _) => DateTime::from_timestamp(i.epochSecond, i.nanoOfSecond as u32),
+            (None, Some(ms)) => DateTime::from_timestamp_millis(ms),
+            (None, None) => None,
+        }
+    }
 }
 
+/// A Log4j2 throwable. Causes and suppressed exceptions nest to any depth.
 #[derive(Serialize, Deserialize)]
 #[allow(non_snake_case)]
-pub struct SubCause {
+pub struct Thrown {
+    #[serde(default)]
     pub commonElementCount: u32,
     pub name: String,
-    pub message: String,
+    pub message: Option<String>,
+    #[serde(default)]
     pub extendedStackTrace: Vec<Trace>,
+    pub cause: Option<Box<Thrown>>,
+    pub suppressed: Option<Vec<Thrown>>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -60,8 +65,12 @@ pub struct Trace {
     pub class: String,
     pub method: String,
     pub file: Option<String>,
+    #[serde(default)]
     pub line: i32,
+    #[serde(default)]
     pub exact: bool,
+    #[serde(default)]
     pub location: String,
+    #[serde(default)]
     pub version: String,
 }
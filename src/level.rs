@@ -0,0 +1,117 @@
+//! Level names and the `--level` filter.
+
+/// The levels grok knows the order of, least severe first.
+pub const LEVELS: [&str; 6] = ["TRACE", "DEBUG", "INFO", "WARN", "ERROR", "FATAL"];
+
+/// The names the various logging libraries use for each of [`LEVELS`], in lower case.
+pub const ALIASES: [(&str, &[&str]); 6] = [
+    ("TRACE", &["trace", "finest", "finer"]),
+    ("DEBUG", &["debug", "fine", "verbose"]),
+    ("INFO", &["info", "information", "informational", "notice"]),
+    ("WARN", &["warn", "warning"]),
+    ("ERROR", &["error", "err", "severe"]),
+    ("FATAL", &["fatal", "critical", "crit", "alert", "emerg", "emergency", "panic", "dpanic"]),
+];
+
+/// Normalizes the level names of the various logging libraries (i.e. `warning`, `err`,
+/// `CRITICAL`, `dpanic`) onto grok's. Custom levels are only upper-cased.
+pub fn normalize(level: &str) -> String {
+    let lower = level.trim().to_lowercase();
+    match ALIASES.iter().find(|(_, names)| names.contains(&lower.as_str())) {
+        Some((level, _)) => level.to_string(),
+        None => level.trim().to_uppercase(),
+    }
+}
+
+/// The lower-case names [`normalize`] maps onto `level`; just the level itself for a custom one.
+pub fn aliases(level: &str) -> Vec<String> {
+    match ALIASES.iter().find(|(l, _)| *l == level) {
+        Some((_, names)) => names.iter().map(|n| n.to_string()).collect(),
+        None => vec![level.to_lowercase()],
+    }
+}
+
+/// The position of a normalized level in [`LEVELS`], or `None` for a custom level.
+pub fn rank(level: &str) -> Option<usize> {
+    LEVELS.iter().position(|l| *l == level)
+}
+
+/// Which events `--level` keeps.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum LevelFilter {
+    #[default]
+    All,
+    /// `WARN`: the level and everything more severe.
+    AtLeast(String),
+    /// `=WARN`, or a custom level: only that level.
+    Exactly(String),
+    /// `OFF`: no events at all.
+    Off,
+}
+
+impl LevelFilter {
+    pub fn parse(spec: &str) -> Result<LevelFilter, String> {
+        let spec = spec.trim();
+        if let Some(exact) = spec.strip_prefix('=') {
+            return match exact.trim() {
+                "" => Err(String::from("Missing level after '='")),
+                level => Ok(LevelFilter::Exactly(normalize(level))),
+            };
+        }
+        match normalize(spec).as_str() {
+            "" => Err(String::from("Missing level")),
+            "ALL" => Ok(LevelFilter::All),
+            "OFF" => Ok(LevelFilter::Off),
+            level if rank(level).is_some() => Ok(LevelFilter::AtLeast(level.to_string())),
+            level => Ok(LevelFilter::Exactly(level.to_string())),
+        }
+    }
+
+    /// Whether an event with `level` is kept. Unless the filter is `OFF`, events without a level
+    /// always are, and so are custom levels under a threshold since they can't be ranked.
+    pub fn matches(&self, level: Option<&str>) -> bool {
+        let Some(level) = level else {
+            return *self != LevelFilter::Off;
+        };
+        match self {
+            LevelFilter::All => true,
+            LevelFilter::AtLeast(min) => match rank(level) {
+                Some(rank) => rank >= self::rank(min).unwrap_or_default(),
+                None => true,
+            },
+            LevelFilter::Exactly(exact) => level == exact,
+            LevelFilter::Off => false,
+        }
+    }
+
+    pub fn is_all(&self) -> bool {
+        *self == LevelFilter::All
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize, LevelFilter};
+
+    #[test]
+    fn filter_levels() {
+        assert_eq!(normalize("warning"), "WARN");
+        assert_eq!(normalize("CRITICAL"), "FATAL");
+        assert_eq!(normalize("audit"), "AUDIT");
+
+        let warn = LevelFilter::parse("warn").unwrap();
+        assert!(warn.matches(Some("ERROR")));
+        assert!(warn.matches(Some("FATAL")));
+        assert!(!warn.matches(Some("INFO")));
+        assert!(warn.matches(None));
+        assert!(warn.matches(Some("AUDIT")));
+
+        let exact = LevelFilter::parse("=WARNING").unwrap();
+        assert_eq!(exact, LevelFilter::Exactly(String::from("WARN")));
+        assert!(!exact.matches(Some("ERROR")));
+        assert_eq!(LevelFilter::parse("audit").unwrap(), LevelFilter::Exactly(String::from("AUDIT")));
+        assert!(!LevelFilter::parse("OFF").unwrap().matches(Some("FATAL")));
+        assert!(!LevelFilter::parse("OFF").unwrap().matches(None));
+        assert!(LevelFilter::parse("=").is_err());
+    }
+}
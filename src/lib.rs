@@ -1 +1,10 @@
+pub mod config;
+pub mod container;
+pub mod event;
+pub mod export;
+pub mod filter;
+pub mod format;
 pub mod json;
+pub mod level;
+pub mod multiline;
+pub mod subscription;
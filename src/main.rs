@@ -3,33 +3,90 @@ extern crate chrono_english;
 extern crate clap;
 extern crate termion;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write;
-use std::io::{self, BufRead};
-use std::ops::Deref;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
 use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 
-use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono::Utc;
 use chrono::prelude::*;
 use chrono_english::{Dialect, parse_date_string};
 use clap::{App, Arg, crate_version};
-use log::{debug, error, warn};
+use log::{debug, error};
+use regex::Regex;
 use rusoto_core::Region;
 use rusoto_logs::{CloudWatchLogs, CloudWatchLogsClient, DescribeLogGroupsRequest, FilterLogEventsRequest};
-use termion::color;
+use termion::{color, style};
 
-use grok::json::JSONMessage;
+use grok::config::ConfigFile;
+use grok::container::Unwrapper;
+use grok::event::{Exception, Style};
+use grok::export::{self, ExportedEvent};
+use grok::filter::{cloudwatch_pattern, merge_patterns, FieldFilter, GrepFilter};
+use grok::format::layout::PatternLayout;
+use grok::format::emf::MetricRecord;
+use grok::format::lambda::{PlatformRecord, Report};
+use grok::format::{default_formats, otlp, Detector, LogFormat};
+use grok::level::LevelFilter;
+use grok::multiline::Reassembler;
+use grok::subscription::{self, Delivery};
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 struct Config {
     region: String,
     nocolor: bool,
-    level: String,
+    level: LevelFilter,
     group: String,
     start_date: Option<String>,
     end_date: Option<String>,
     pattern: Option<String>,
+    collapse: Vec<String>,
+    layouts: Vec<String>,
+    record_start: Option<Regex>,
+    invocations: bool,
+    emf: String,
+    metrics: bool,
+    fields: Vec<FieldFilter>,
+    grep: GrepFilter,
+    config_file: ConfigFile,
+    mappings: Vec<String>,
+}
+
+/// Where a printer writes its lines; printers of the same reader share it.
+type Output = Arc<Mutex<dyn io::Write + Send>>;
+
+/// How long standard input may be quiet before the event being reassembled is printed.
+const IDLE_FLUSH: Duration = Duration::from_millis(500);
+
+/// Renders the events of one reader, keeping the state that spans events.
+struct Printer {
+    config: Config,
+    out: Output,
+    detector: Detector,
+    /// Lambda invocations waiting for their REPORT line, by log stream.
+    invocations: HashMap<String, Invocation>,
+    /// EMF totals by namespace, dimensions, metric name and unit.
+    metrics: BTreeMap<(String, String, String, String), MetricTotals>,
+}
+
+struct Invocation {
+    request_id: String,
+    lines: Vec<String>,
+}
+
+#[derive(Default)]
+struct MetricTotals {
+    sum: f64,
+    count: usize,
+    min: f64,
+    max: f64,
 }
 
 #[tokio::main]
@@ -64,11 +121,11 @@ async fn main() {
         .arg(
             Arg::with_name("level")
                 .short("l")
-                .possible_values(&["ALL", "TRACE", "DEBUG", "WARN", "INFO", "ERROR"])
                 .default_value("ALL")
                 .long("level")
                 .takes_value(true)
-                .help("filter to a certain log level"),
+                .validator(|l| LevelFilter::parse(&l).map(|_| ()))
+                .help("minimum level to show (ALL, TRACE, DEBUG, INFO, WARN, ERROR, FATAL or OFF), =LEVEL for only that level, or a custom level"),
         )
         .arg(
             Arg::with_name("pattern")
@@ -84,6 +141,16 @@ async fn main() {
                 .takes_value(true)
                 .help("CSV of all groups to read"),
         )
+        .arg(
+            Arg::with_name("file")
+                .short("f")
+                .long("file")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .conflicts_with("groups")
+                .help("log file to read instead of stdin"),
+        )
         .arg(
             Arg::with_name("list")
                 .long("list")
@@ -97,6 +164,97 @@ async fn main() {
                 .takes_value(false)
                 .help("disable color highlighting"),
         )
+        .arg(
+            Arg::with_name("collapse")
+                .long("collapse")
+                .takes_value(true)
+                .help("CSV of package prefixes whose stack frames are collapsed (i.e. org.springframework.,sun.reflect.)"),
+        )
+        .arg(
+            Arg::with_name("layout")
+                .long("layout")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Log4j/Logback PatternLayout of plain-text lines (i.e. \"%d{ISO8601} [%t] %-5level %logger{36} - %msg%n\")"),
+        )
+        .arg(
+            Arg::with_name("record-start")
+                .long("record-start")
+                .takes_value(true)
+                .help("regex matching the first line of each event on stdin; other lines continue the previous event"),
+        )
+        .arg(
+            Arg::with_name("invocations")
+                .long("invocations")
+                .takes_value(false)
+                .help("group Lambda output by invocation, with its duration, memory and cold start"),
+        )
+        .arg(
+            Arg::with_name("emf")
+                .long("emf")
+                .possible_values(&["show", "hide", "only"])
+                .default_value("show")
+                .takes_value(true)
+                .help("show, hide or only show Embedded Metric Format records"),
+        )
+        .arg(
+            Arg::with_name("metrics")
+                .long("metrics")
+                .takes_value(false)
+                .help("total the EMF metric values per dimension over the time range instead of printing events"),
+        )
+        .arg(
+            Arg::with_name("field")
+                .long("field")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("only show events whose field or column matches key=value, * matching anything (i.e. eventName=Delete*)"),
+        )
+        .arg(
+            Arg::with_name("grep")
+                .long("grep")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("only show events whose message, logger or fields match the regex, highlighting the matches"),
+        )
+        .arg(
+            Arg::with_name("grep-v")
+                .long("grep-v")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("hide events whose message, logger or fields match the regex"),
+        )
+        .arg(
+            Arg::with_name("ignore-case")
+                .short("i")
+                .long("ignore-case")
+                .takes_value(false)
+                .help("match --grep and --grep-v regexes case-insensitively"),
+        )
+        .arg(
+            Arg::with_name("no-server-filter")
+                .long("no-server-filter")
+                .takes_value(false)
+                .help("apply --level and --field only after download, for groups with plain-text lines"),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .takes_value(true)
+                .help("JSON config file with field mappings [default: ~/.grok.json]"),
+        )
+        .arg(
+            Arg::with_name("mapping")
+                .long("mapping")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("name of a config file field mapping to use regardless of its groups (i.e. on stdin)"),
+        )
         .get_matches();
 
     let region = matches.value_of("region").unwrap_or("us-east-1");
@@ -118,7 +276,77 @@ async fn main() {
         if matches.is_present("pattern") {
             pattern = Some(String::from(matches.value_of("pattern").unwrap()));
         }
-        let level = matches.value_of("level").unwrap_or("ALL");
+        let level = LevelFilter::parse(matches.value_of("level").unwrap_or("ALL")).unwrap_or_default();
+        let collapse: Vec<String> = match matches.value_of("collapse") {
+            Some(c) => c.split(',').filter(|p| !p.is_empty()).map(String::from).collect(),
+            None => vec![],
+        };
+        let layouts: Vec<String> = match matches.values_of("layout") {
+            Some(l) => l.map(String::from).collect(),
+            None => vec![],
+        };
+        for layout in &layouts {
+            if let Err(e) = PatternLayout::new(layout) {
+                clap::Error::with_description(&e, clap::ErrorKind::InvalidValue).exit();
+            }
+        }
+        let record_start = matches.value_of("record-start").map(|r| {
+            Regex::new(r).unwrap_or_else(|e| {
+                clap::Error::with_description(&e.to_string(), clap::ErrorKind::InvalidValue).exit()
+            })
+        });
+        let fields: Vec<FieldFilter> = matches
+            .values_of("field")
+            .into_iter()
+            .flatten()
+            .map(|f| FieldFilter::parse(f).unwrap_or_else(|e| clap::Error::with_description(&e, clap::ErrorKind::InvalidValue).exit()))
+            .collect();
+        let config_file = match matches.value_of("config") {
+            Some(path) => ConfigFile::load(Path::new(path))
+                .unwrap_or_else(|e| clap::Error::with_description(&e, clap::ErrorKind::InvalidValue).exit()),
+            None => match env::var_os("HOME").map(|home| Path::new(&home).join(".grok.json")) {
+                Some(path) if path.exists() => ConfigFile::load(&path)
+                    .unwrap_or_else(|e| clap::Error::with_description(&e, clap::ErrorKind::InvalidValue).exit()),
+                _ => ConfigFile::default(),
+            },
+        };
+        let mappings: Vec<String> = matches.values_of("mapping").into_iter().flatten().map(String::from).collect();
+        if let Some(name) = mappings.iter().find(|n| !config_file.mappings.iter().any(|m| &m.name == *n)) {
+            clap::Error::with_description(&format!("No mapping named '{}' in the config file", name), clap::ErrorKind::InvalidValue).exit();
+        }
+        // let CloudWatch drop what the filters would, unless the output needs events without a
+        // level (Lambda platform lines, EMF records) or the level isn't where the pattern looks
+        // (layouts, record starts, mappings)
+        let invocations = matches.is_present("invocations");
+        let emf = matches.value_of("emf").unwrap_or("show").to_string();
+        let metrics = matches.is_present("metrics");
+        let custom = !layouts.is_empty() || record_start.is_some() || !mappings.is_empty();
+        if !matches.is_present("no-server-filter") && !custom && !invocations && !metrics && emf == "show" {
+            pattern = merge_patterns(pattern.as_deref(), cloudwatch_pattern(&level, &fields));
+            debug!("Filter pattern: {:?}", pattern);
+        }
+        let values = |name: &str| -> Vec<String> { matches.values_of(name).into_iter().flatten().map(String::from).collect() };
+        let grep = GrepFilter::new(&values("grep"), &values("grep-v"), matches.is_present("ignore-case"))
+            .unwrap_or_else(|e| clap::Error::with_description(&e, clap::ErrorKind::InvalidValue).exit());
+        let base_config = Config {
+            region: region.to_string(),
+            nocolor,
+            level,
+            group: String::new(),
+            start_date,
+            end_date,
+            pattern,
+            collapse,
+            layouts,
+            record_start,
+            invocations,
+            emf,
+            metrics,
+            fields,
+            grep,
+            config_file,
+            mappings,
+        };
         if matches.is_present("groups") {
             let mut handles = vec![];
             let mut groups: Vec<String> = vec![];
@@ -142,20 +370,15 @@ async fn main() {
             if groups.len() > 10 {
                 println!("Only showing first 8 groups");
             }
-            for x in (0..8) {
+            for x in 0..8 {
                 let group_o = groups.get(x);
                 if group_o.is_none() {
                     break;
                 }
                 let group = group_o.unwrap();
                 let config = Config {
-                    region: region.to_string(),
-                    nocolor: nocolor,
-                    level: level.to_string(),
                     group: group.to_string(),
-                    start_date: start_date.clone(),
-                    end_date: end_date.clone(),
-                    pattern: pattern.clone(),
+                    ..base_config.clone()
                 };
                 let jh = tokio::spawn(async move {
                     println!("Reading from group {}", config.group);
@@ -164,19 +387,24 @@ async fn main() {
                 handles.push(jh);
             }
             futures::future::join_all(handles).await;
+        } else if let Some(paths) = matches.values_of("file") {
+            let paths: Vec<String> = paths.map(String::from).collect();
+            read_from_files(base_config, &paths);
         } else {
-            read_from_stdin(level.to_string(), nocolor);
+            read_from_stdin(base_config);
         }
     }
 }
 
 async fn read_from_cloudwatch(config: Config) {
+    let mut printer = Printer::new(config.clone());
     let mut end;
     let mut start;
-    let mut watch = true;
-    if config.end_date.is_some() {
+    // totals are only meaningful over a fixed window
+    let mut watch = !config.metrics;
+    if let Some(end_str) = &config.end_date {
         let end_date =
-            parse_date_string(&*config.end_date.unwrap(), Local::now(), Dialect::Us);
+            parse_date_string(end_str, Local::now(), Dialect::Us);
         match end_date {
             Ok(v) => {
                 end = v.timestamp_millis();
@@ -189,9 +417,9 @@ async fn read_from_cloudwatch(config: Config) {
     } else {
         end = Utc::now().timestamp_millis();
     }
-    if config.start_date.is_some() {
+    if let Some(start_str) = &config.start_date {
         let start_date =
-            parse_date_string(&*config.start_date.unwrap(), Local::now(), Dialect::Us);
+            parse_date_string(start_str, Local::now(), Dialect::Us);
         match start_date {
             Ok(v) => {
                 start = v.timestamp_millis();
@@ -205,20 +433,20 @@ async fn read_from_cloudwatch(config: Config) {
         start = end - 120000;
     }
     'outer: loop {
-        let mut get_log_req: FilterLogEventsRequest = Default::default();
-        get_log_req.log_group_name = config.group.clone();
         // we have to account for the ~10s it takes to ingest the logs, so we always look back 10 seconds
-        get_log_req.start_time = Some(start - 10000);
-        get_log_req.end_time = Some(end - 10000);
-        if config.pattern.is_some() {
-            get_log_req.filter_pattern = config.pattern.clone();
-        }
+        let mut get_log_req = FilterLogEventsRequest {
+            log_group_name: config.group.clone(),
+            start_time: Some(start - 10000),
+            end_time: Some(end - 10000),
+            filter_pattern: config.pattern.clone(),
+            ..Default::default()
+        };
         debug!("Start: {}", get_log_req.start_time.unwrap());
         debug!("End: {}", get_log_req.end_time.unwrap());
         debug!("Group: {}", get_log_req.log_group_name);
         debug!("Range: {}", end - start);
 
-        let client = CloudWatchLogsClient::new(Region::from_str(&*config.region).unwrap());
+        let client = CloudWatchLogsClient::new(Region::from_str(&config.region).unwrap());
         let mut next_token = None;
         'inner: loop {
             get_log_req.next_token = next_token;
@@ -228,21 +456,12 @@ async fn read_from_cloudwatch(config: Config) {
                 .unwrap_or_else(|e| panic!("Failed on get log events: {}", e));
 
             debug!("Found {} events", get_log_resp.events.clone().unwrap().len());
-            for event in get_log_resp.events {
-                for outp in event {
+            if let Some(events) = get_log_resp.events {
+                for outp in events {
                     let msg = outp.message.unwrap();
-                    debug!("{}", msg.clone().to_string());
+                    debug!("{}", msg);
                     let stream = outp.log_stream_name.unwrap();
-                    let line = create_log_string(
-                        config.level.clone(),
-                        config.group.clone(),
-                        stream,
-                        config.nocolor,
-                        msg.to_string(),
-                    );
-                    if line.len() > 0 {
-                        println!("{}", line);
-                    }
+                    printer.print(&stream, None, &msg);
                 }
             }
             next_token = get_log_resp.next_token;
@@ -252,28 +471,31 @@ async fn read_from_cloudwatch(config: Config) {
             thread::sleep(std::time::Duration::from_millis(100));
         }
         if !watch {
+            printer.finish();
             break 'outer;
         }
         thread::sleep(std::time::Duration::from_millis(2000));
-        start = end.clone();
+        start = end;
         end = Utc::now().timestamp_millis();
     };
 }
 
 async fn list_groups(region: &str) {
-    let client = CloudWatchLogsClient::new(Region::from_str(&*region).unwrap());
+    let client = CloudWatchLogsClient::new(Region::from_str(region).unwrap());
     let mut next_token = None;
     loop {
-        let mut desc_groups_req: DescribeLogGroupsRequest = Default::default();
-        desc_groups_req.next_token = next_token;
+        let desc_groups_req = DescribeLogGroupsRequest {
+            next_token,
+            ..Default::default()
+        };
         let desc_groups_resp = client
             .describe_log_groups(desc_groups_req)
             .await
             .unwrap_or_else(|e| panic!("Failed on get log groups: {}", e));
 
         next_token = desc_groups_resp.next_token;
-        for event in desc_groups_resp.log_groups {
-            for lg in event {
+        if let Some(log_groups) = desc_groups_resp.log_groups {
+            for lg in log_groups {
                 let msg = lg.log_group_name.unwrap();
                 println!("{}", msg);
             }
@@ -286,21 +508,23 @@ async fn list_groups(region: &str) {
 }
 
 async fn get_groups(region: &str) -> Vec<String> {
-    let client = CloudWatchLogsClient::new(Region::from_str(&*region).unwrap());
+    let client = CloudWatchLogsClient::new(Region::from_str(region).unwrap());
     let mut next_token = None;
     let mut groups = vec![];
 
     loop {
-        let mut desc_groups_req: DescribeLogGroupsRequest = Default::default();
-        desc_groups_req.next_token = next_token;
+        let desc_groups_req = DescribeLogGroupsRequest {
+            next_token,
+            ..Default::default()
+        };
         let desc_groups_resp = client
             .describe_log_groups(desc_groups_req)
             .await
             .unwrap_or_else(|e| panic!("Failed on get log groups: {}", e));
 
         next_token = desc_groups_resp.next_token;
-        for event in desc_groups_resp.log_groups {
-            for lg in event {
+        if let Some(log_groups) = desc_groups_resp.log_groups {
+            for lg in log_groups {
                 let msg = lg.log_group_name.unwrap();
                 groups.push(msg.clone());
             }
@@ -310,180 +534,965 @@ async fn get_groups(region: &str) -> Vec<String> {
         }
         thread::sleep(std::time::Duration::from_millis(100));
     };
-    return groups;
+    groups
+}
+
+fn read_from_stdin(config: Config) {
+    let mut printer = Printer::new(config);
+    read_input(&mut printer, "", LiveInput::stdin(IDLE_FLUSH));
+    printer.finish();
+}
+
+/// Standard input, read on a thread of its own so that a pause in a live stream reaches
+/// `read_lines` as a `WouldBlock` error, once per pause and always between two lines.
+struct LiveInput {
+    lines: Receiver<Vec<u8>>,
+    idle: Duration,
+    line: Vec<u8>,
+    pos: usize,
+    paused: bool,
+}
+
+impl LiveInput {
+    fn stdin(idle: Duration) -> LiveInput {
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            let mut stdin = io::stdin().lock();
+            loop {
+                let mut line = vec![];
+                match stdin.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        error!("Unable to read input {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        LiveInput::new(lines, idle)
+    }
+
+    fn new(lines: Receiver<Vec<u8>>, idle: Duration) -> LiveInput {
+        LiveInput {
+            lines,
+            idle,
+            line: vec![],
+            pos: 0,
+            // the first line is waited for however long it takes
+            paused: true,
+        }
+    }
+}
+
+impl Read for LiveInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for LiveInput {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.line.len() {
+            let next = if self.paused {
+                self.lines.recv().ok()
+            } else {
+                match self.lines.recv_timeout(self.idle) {
+                    Ok(line) => Some(line),
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.paused = true;
+                        return Err(io::ErrorKind::WouldBlock.into());
+                    }
+                    Err(RecvTimeoutError::Disconnected) => None,
+                }
+            };
+            match next {
+                Some(line) => {
+                    self.line = line;
+                    self.pos = 0;
+                    self.paused = false;
+                }
+                None => return Ok(&[]),
+            }
+        }
+        Ok(&self.line[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt;
+    }
+}
+
+/// Reads each file in turn, using its path as the stream name.
+fn read_from_files(config: Config, paths: &[String]) {
+    let mut printer = Printer::new(config);
+    for path in paths {
+        match File::open(path) {
+            Ok(file) => read_input(&mut printer, path, BufReader::new(file)),
+            Err(e) => error!("Unable to open {}: {}", path, e),
+        }
+    }
+    printer.finish();
+}
+
+/// Prints the events of `input`, which is either subscription filter payloads, an exported
+/// document or log lines.
+fn read_input(printer: &mut Printer, stream: &str, mut input: impl BufRead) {
+    let head = match read_head(&mut input) {
+        Ok(head) => head,
+        Err(e) => {
+            error!("Unable to read input {}", e);
+            return;
+        }
+    };
+    let mut input = io::Cursor::new(head.clone()).chain(input);
+    if subscription::is_payload(&head) {
+        let mut data = vec![];
+        if let Err(e) = read_document(&mut input, &mut data) {
+            error!("Unable to read input {}", e);
+            return;
+        }
+        match subscription::decode(&data) {
+            Ok(deliveries) => print_deliveries(printer, deliveries),
+            Err(e) => error!("{}", e),
+        }
+        return;
+    }
+    if !export::is_export(&String::from_utf8_lossy(&head)) {
+        read_lines(printer, stream, input);
+        return;
+    }
+    let mut data = vec![];
+    if let Err(e) = read_document(&mut input, &mut data) {
+        error!("Unable to read input {}", e);
+        return;
+    }
+    let document = String::from_utf8_lossy(&data);
+    match export::parse(&document) {
+        Some(events) => print_export(printer, events),
+        None => read_lines(printer, stream, document.as_bytes()),
+    }
+}
+
+/// How much of the input is looked at to tell documents from log lines.
+const HEAD_LEN: usize = 4096;
+
+/// Consumes the start of `input`: up to the first token inside a leading JSON object, so that a
+/// document pretty-printed over several lines can be recognized, or at most `HEAD_LEN` bytes.
+fn read_head(input: &mut impl BufRead) -> io::Result<Vec<u8>> {
+    let mut head = vec![];
+    while head.len() < HEAD_LEN {
+        let available = match input.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+            Err(e) => return Err(e),
+        };
+        if available.is_empty() {
+            break;
+        }
+        let n = available.len();
+        head.extend_from_slice(available);
+        input.consume(n);
+        let start = head.trim_ascii_start();
+        match start.strip_prefix(b"{") {
+            Some(rest) if rest.trim_ascii_start().is_empty() => {}
+            Some(_) => break,
+            None if start.is_empty() => {}
+            None => break,
+        }
+    }
+    Ok(head)
+}
+
+/// Reads the rest of `input`, waiting out the pauses of a live input.
+fn read_document(input: &mut impl BufRead, data: &mut Vec<u8>) -> io::Result<()> {
+    loop {
+        match input.read_to_end(data) {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+            result => return result.map(|_| ()),
+        }
+    }
+}
+
+/// Prints the events of subscription deliveries under their original group and stream.
+fn print_deliveries(printer: &mut Printer, deliveries: Vec<Delivery>) {
+    let events = deliveries
+        .into_iter()
+        .flat_map(|d| {
+            d.log_events.into_iter().map(move |e| ExportedEvent {
+                group: Some(d.log_group.clone()),
+                stream: d.log_stream.clone(),
+                timestamp: DateTime::from_timestamp_millis(e.timestamp),
+                message: e.message,
+            })
+        })
+        .collect();
+    print_export(printer, events);
+}
+
+/// Prints exported events under their own stream names. Events of another group than the
+/// printer's go through a printer of that group, so its mappings apply.
+fn print_export(printer: &mut Printer, events: Vec<ExportedEvent>) {
+    let mut printers: BTreeMap<String, Printer> = BTreeMap::new();
+    for event in events {
+        let target = match event.group {
+            Some(group) if group != printer.config.group => printers.entry(group.clone()).or_insert_with(|| {
+                Printer::with_output(
+                    Config {
+                        group,
+                        ..printer.config.clone()
+                    },
+                    printer.out.clone(),
+                )
+            }),
+            _ => &mut *printer,
+        };
+        target.print(&event.stream, event.timestamp, &event.message);
+    }
+    for (_, mut p) in printers {
+        p.finish();
+    }
 }
 
-fn read_from_stdin(level: String, nocolor: bool) {
-    let stdin = io::stdin();
-    for line in stdin.lock().lines() {
+/// Prints the events of `input`, unwrapping container log records and reassembling multi-line
+/// events first.
+fn read_lines(printer: &mut Printer, stream: &str, input: impl BufRead) {
+    let mut unwrapper = Unwrapper::new();
+    let mut reassembler = Reassembler::new(printer.config.record_start.clone());
+    for line in input.lines() {
         match line {
             Ok(l) => {
-                let line = create_log_string(level.to_string(), String::new(), String::new(),
-                                             nocolor, l);
-                if line.len() > 0 {
-                    println!("{}", line);
+                if let Some(event) = unwrapper.push(l).and_then(|l| reassembler.push(l)) {
+                    printer.print(stream, None, &event);
+                }
+            }
+            // a live input went quiet, so the event being reassembled is as complete as it gets
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                if let Some(event) = reassembler.flush() {
+                    printer.print(stream, None, &event);
                 }
             }
             Err(e) => {
-                error!("Unable to parse input {}", e.to_string());
+                error!("Unable to parse input {}", e);
             }
         }
     }
+    for line in unwrapper.flush() {
+        if let Some(event) = reassembler.push(line) {
+            printer.print(stream, None, &event);
+        }
+    }
+    if let Some(event) = reassembler.flush() {
+        printer.print(stream, None, &event);
+    }
 }
 
-fn create_log_string(
-    level: String,
-    group: String,
-    stream: String,
-    nocolor: bool,
-    line: String,
-) -> String {
-    debug!("Line: {}", line.clone());
-    debug!("Group: {}", group.clone());
-    debug!("Stream: {}", stream.clone());
-    debug!("Level: {}", level.clone());
+impl Printer {
+    fn new(config: Config) -> Printer {
+        Printer::with_output(config, Arc::new(Mutex::new(io::stdout())))
+    }
+
+    fn with_output(config: Config, out: Output) -> Printer {
+        // the config file mappings for this group, the built-in formats and then the user's
+        // layouts, which were validated at startup
+        let mut formats: Vec<Box<dyn LogFormat>> = vec![];
+        for mapping in config.config_file.mappings_for(&config.group, &config.mappings) {
+            formats.push(Box::new(mapping));
+        }
+        formats.extend(default_formats());
+        for layout in &config.layouts {
+            if let Ok(layout) = PatternLayout::new(layout) {
+                formats.push(Box::new(layout));
+            }
+        }
+        Printer {
+            config,
+            out,
+            detector: Detector::new(formats),
+            invocations: HashMap::new(),
+            metrics: BTreeMap::new(),
+        }
+    }
+
+    /// Prints one event. `timestamp` is the time the event was received, for events that don't
+    /// carry their own.
+    fn print(&mut self, stream: &str, timestamp: Option<DateTime<Utc>>, message: &str) {
+        if let Some(records) = otlp::split_batch(message) {
+            for record in records {
+                self.print(stream, timestamp, &record);
+            }
+            return;
+        }
+        if self.config.metrics || self.config.emf != "show" {
+            let records = MetricRecord::parse(message);
+            if self.config.metrics {
+                for record in records.iter().flatten() {
+                    self.add_metrics(record);
+                }
+                return;
+            }
+            if records.is_some() == (self.config.emf == "hide") {
+                return;
+            }
+        }
+        if self.config.invocations {
+            match PlatformRecord::parse(message) {
+                Some(PlatformRecord::Start { request_id, .. }) => {
+                    self.print_invocation(stream, None);
+                    self.invocations.insert(stream.to_string(), Invocation { request_id, lines: vec![] });
+                    return;
+                }
+                Some(PlatformRecord::End { .. }) => return,
+                Some(PlatformRecord::Report(report)) => {
+                    self.print_invocation(stream, Some(report));
+                    return;
+                }
+                _ => {}
+            }
+        }
+        let line = create_log_string(&self.config, &mut self.detector, stream, timestamp, message);
+        if line.is_empty() {
+            return;
+        }
+        match self.invocations.get_mut(stream) {
+            Some(invocation) => invocation.lines.push(line),
+            None => self.println(&line),
+        }
+    }
+
+    /// Prints the open invocation of `stream` under a header summarizing its REPORT line. With a
+    /// level, field or grep filter, invocations without any matching events are skipped.
+    fn print_invocation(&mut self, stream: &str, report: Option<Report>) {
+        let invocation = self.invocations.remove(stream);
+        let request_id = match (&invocation, &report) {
+            (Some(i), _) => i.request_id.clone(),
+            (None, Some(r)) => r.request_id.clone(),
+            (None, None) => return,
+        };
+        let lines = invocation.map(|i| i.lines).unwrap_or_default();
+        let filtered = !self.config.level.is_all() || !self.config.fields.is_empty() || !self.config.grep.is_empty();
+        if filtered && lines.is_empty() {
+            return;
+        }
+        let nocolor = self.config.nocolor;
+        let mut header = String::new();
+        let _ = write!(
+            header,
+            "{}{} {} == Invocation {}{}",
+            color_str(!nocolor, &color::Magenta),
+            self.config.group,
+            stream,
+            request_id,
+            color_str(!nocolor, &color::Reset)
+        );
+        match report {
+            Some(r) => {
+                let _ = write!(
+                    header,
+                    " {} ms (billed {} ms), {}/{} MB",
+                    r.duration_ms, r.billed_duration_ms, r.max_memory_used_mb, r.memory_size_mb
+                );
+                if let Some(init) = r.init_duration_ms {
+                    let _ = write!(
+                        header,
+                        ", {}cold start{} (init {} ms)",
+                        color_str(!nocolor, &color::Yellow),
+                        color_str(!nocolor, &color::Reset),
+                        init
+                    );
+                }
+            }
+            None => header.push_str(" (incomplete)"),
+        }
+        self.println(&header);
+        for line in lines {
+            self.println(&line);
+        }
+    }
+
+    fn add_metrics(&mut self, record: &MetricRecord) {
+        let dimensions: Vec<String> = record.dimensions.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        for metric in &record.metrics {
+            let key = (
+                record.namespace.clone(),
+                dimensions.join(","),
+                metric.name.clone(),
+                metric.unit.clone().unwrap_or_default(),
+            );
+            let totals = self.metrics.entry(key).or_default();
+            for &value in &metric.values {
+                if totals.count == 0 || value < totals.min {
+                    totals.min = value;
+                }
+                if totals.count == 0 || value > totals.max {
+                    totals.max = value;
+                }
+                totals.sum += value;
+                totals.count += 1;
+            }
+        }
+    }
+
+    /// Prints whatever is still buffered once the input is exhausted.
+    fn finish(&mut self) {
+        let streams: Vec<String> = self.invocations.keys().cloned().collect();
+        for stream in streams {
+            self.print_invocation(&stream, None);
+        }
+        let nocolor = self.config.nocolor;
+        for ((namespace, dimensions, name, unit), totals) in &self.metrics {
+            let _ = writeln!(
+                self.out.lock().unwrap(),
+                "{}{} {}{} {}{}{}: sum={} count={} min={} max={} avg={} {}",
+                color_str(!nocolor, &color::Magenta),
+                namespace,
+                color_str(!nocolor, &color::Reset),
+                dimensions,
+                color_str(!nocolor, &color::Cyan),
+                name,
+                color_str(!nocolor, &color::Reset),
+                totals.sum,
+                totals.count,
+                totals.min,
+                totals.max,
+                if totals.count > 0 { totals.sum / totals.count as f64 } else { 0.0 },
+                if unit == "None" { "" } else { unit },
+            );
+        }
+        self.metrics.clear();
+    }
+
+    fn println(&self, line: &str) {
+        let _ = writeln!(self.out.lock().unwrap(), "{}", line);
+    }
+}
+
+fn create_log_string(config: &Config, detector: &mut Detector, stream: &str, timestamp: Option<DateTime<Utc>>, line: &str) -> String {
+    let level = &config.level;
+    let group = &config.group;
+    let nocolor = config.nocolor;
+    debug!("Line: {}", line);
+    debug!("Group: {}", group);
+    debug!("Stream: {}", stream);
+    debug!("Level: {:?}", level);
     let mut out_line = String::new();
-    let j = serde_json::from_str(&line);
-    match j {
-        Ok(l) => {
-            let jm: JSONMessage = l;
-            if level == "ALL" || level == jm.level {
-                let dt = NaiveDateTime::from_timestamp(jm.instant.unwrap()
-                                                           .epochSecond, 0);
-                // let dt = Utc.timestamp((jm.timeMillis.unwrap()) as i64, 0);
-                write!(
+    match detector.parse(stream, line) {
+        Some(event) => {
+            let event_level = event.level.as_deref().unwrap_or("");
+            if level.matches(event.level.as_deref()) && config.fields.iter().all(|f| f.matches(&event)) && config.grep.matches(&event) {
+                let dt = match event.timestamp.or(timestamp) {
+                    Some(ts) => ts.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    None => String::from("-"),
+                };
+                let origin = match &event.source {
+                    Some(source) => source.clone(),
+                    None => format!("{} {}", group, stream),
+                };
+                let message = if event.columns.is_empty() {
+                    highlight(config, &event.message)
+                } else {
+                    let columns: Vec<String> = event
+                        .columns
+                        .iter()
+                        .map(|c| {
+                            let key = match &c.key {
+                                Some(key) => format!("{}{}{}=", color_str(!nocolor, &color::Cyan), key, color_str(!nocolor, &color::Reset)),
+                                None => String::new(),
+                            };
+                            format!("{}{}{}{}", key, style_str(!nocolor, c.style), highlight(config, &c.value()), color_str(!nocolor, &color::Reset))
+                        })
+                        .collect();
+                    columns.join(" ")
+                };
+                let _ = write!(
                     out_line,
-                    "{}{} {} -- {} [{}] {}{} {}{} - {}{}{}",
+                    "{}{} -- {} [{}] {}{} {}{} - {}{}{}",
                     color_str(!nocolor, &color::Reset),
-                    group,
-                    stream,
+                    origin,
                     dt,
-                    jm.thread,
+                    event.thread.as_deref().unwrap_or("-"),
                     color_str(!nocolor, &color::Magenta),
-                    jm.level,
+                    event_level,
                     color_str(!nocolor, &color::Reset),
-                    jm.loggerName,
-                    if jm.level == "ERROR" {
+                    highlight(config, event.logger.as_deref().unwrap_or("")),
+                    if event_level == "ERROR" || event_level == "FATAL" {
                         color_str(!nocolor, &color::Red)
-                    } else if jm.level == "WARN" {
+                    } else if event_level == "WARN" {
                         color_str(!nocolor, &color::Yellow)
                     } else {
                         color_str(!nocolor, &color::Cyan)
                     },
-                    jm.message,
+                    message,
                     color_str(!nocolor, &color::Reset)
                 );
 
-                match jm.contextMap {
-                    Some(m) => {
-                        if !m.is_empty() {
-                            writeln!(out_line, "Context map: ");
-                            for (k, v) in m {
-                                writeln!(
-                                    out_line,
-                                    "\t {} = {}", k, v);
-                            }
-                        }
-                    }
-                    None => {
-                        // swallow
+                if !event.fields.is_empty() {
+                    let _ = writeln!(out_line, "Context map: ");
+                    for (k, v) in &event.fields {
+                        let _ = writeln!(
+                            out_line,
+                            "\t {} = {}", k, highlight(config, v));
                     }
                 }
-                //TODO don't really like this level of nesting, but leaving it for now
-                match jm.thrown {
-                    Some(t) => {
-                        writeln!(out_line, "Stacktrace: {} - {}", t.name, t.message.unwrap_or("none".to_string()));
-                        for trace in t.extendedStackTrace {
-                            writeln!(
-                                out_line,
-                                "\t at {}{}.{} ({}:{}) [{}]{}",
-                                color_str(!nocolor, &color::Red),
-                                trace.class,
-                                trace.method,
-                                trace.file.unwrap_or("Unknown".to_string()),
-                                trace.line,
-                                trace.location,
-                                color_str(!nocolor, &color::Reset)
-                            );
-                        }
-                        match t.cause {
-                            Some(c) => {
-                                writeln!(out_line, "Caused by: {} - {}", c.name, c.message);
-                                for ctrace in c.extendedStackTrace {
-                                    writeln!(
-                                        out_line,
-                                        "\t at {}{}.{} ({}:{}) [{}]{}",
-                                        color_str(!nocolor, &color::Red),
-                                        ctrace.class,
-                                        ctrace.method,
-                                        ctrace.file.unwrap_or("Unknown".to_string()),
-                                        ctrace.line,
-                                        ctrace.location,
-                                        color_str(!nocolor, &color::Reset)
-                                    );
-                                }
-                                match c.cause {
-                                    Some(sc) => {
-                                        writeln!(out_line, "Caused by: {} - {}", sc.name, sc.message);
-                                        for sctrace in sc.extendedStackTrace {
-                                            writeln!(
-                                                out_line,
-                                                "\t at {}{}.{} ({}:{}) [{}]{}",
-                                                color_str(!nocolor, &color::Red),
-                                                sctrace.class,
-                                                sctrace.method,
-                                                sctrace.file.unwrap_or("Unknown".to_string()),
-                                                sctrace.line,
-                                                sctrace.location,
-                                                color_str(!nocolor, &color::Reset)
-                                            );
-                                        }
-                                    }
-                                    None => {
-                                        //swallow
-                                    }
-                                }
-                            }
-                            None => {
-                                //swallow
-                            }
-                        }
-                    }
-                    None => {
-                        //swallow
-                    }
+                if let Some(e) = &event.exception {
+                    write_exception(&mut out_line, e, "Stacktrace", 0, config);
                 }
             }
         }
-        Err(_e) => {
-            warn!("Exception: {}", _e);
-            write!(out_line, "{} {} -- {}", group.clone(), stream.clone(), line);
+        // unparsed lines have no fields to match, and no level to keep them under OFF
+        None if !config.fields.is_empty() || !config.grep.matches_line(line) || !level.matches(None) => {}
+        None => {
+            debug!("No format matched line");
+            let _ = write!(out_line, "{} {} -- ", group, stream);
+            if let Some(ts) = timestamp {
+                let _ = write!(out_line, "{} ", ts.format("%Y-%m-%d %H:%M:%S"));
+            }
+            out_line.push_str(&highlight(config, line));
+        }
+    }
+    out_line
+}
+
+/// Shows the `--grep` matches in `text` in reverse video, which keeps the surrounding colors.
+fn highlight(config: &Config, text: &str) -> String {
+    if config.nocolor {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut last = 0;
+    for (start, end) in config.grep.spans(text) {
+        let _ = write!(out, "{}{}{}{}", &text[last..start], style::Invert, &text[start..end], style::NoInvert);
+        last = end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+fn write_exception(out_line: &mut String, e: &Exception, heading: &str, depth: usize, config: &Config) {
+    let indent = "  ".repeat(depth);
+    let nocolor = config.nocolor;
+    let _ = writeln!(
+        out_line,
+        "{}{}: {} - {}",
+        indent,
+        heading,
+        highlight(config, &e.name),
+        highlight(config, e.message.as_deref().unwrap_or("none"))
+    );
+    // like the JVM, frames shared with the enclosing trace are elided as "... N more"
+    let common = e.common_frames.min(e.frames.len());
+    let own_frames = &e.frames[..e.frames.len() - common];
+    let mut collapsed: Vec<&str> = vec![];
+    let mut collapsed_count = 0;
+    for frame in own_frames {
+        let symbol = frame.symbol();
+        if let Some(prefix) = config.collapse.iter().find(|p| symbol.starts_with(p.as_str())) {
+            collapsed_count += 1;
+            if !collapsed.contains(&prefix.as_str()) {
+                collapsed.push(prefix);
+            }
+            continue;
+        }
+        write_collapsed(out_line, &indent, collapsed_count, &collapsed);
+        collapsed_count = 0;
+        collapsed.clear();
+        let _ = write!(
+            out_line,
+            "{}\t at {}{} ({}",
+            indent,
+            color_str(!nocolor, &color::Red),
+            symbol,
+            frame.file.as_deref().unwrap_or("Unknown"),
+        );
+        if let Some(line) = frame.line {
+            let _ = write!(out_line, ":{}", line);
+        }
+        let _ = write!(out_line, ")");
+        if let Some(location) = &frame.location {
+            let _ = write!(out_line, " [{}]", location);
         }
+        let _ = writeln!(out_line, "{}", color_str(!nocolor, &color::Reset));
+    }
+    write_collapsed(out_line, &indent, collapsed_count, &collapsed);
+    if common > 0 {
+        let _ = writeln!(out_line, "{}\t ... {} more", indent, common);
+    }
+    for (i, s) in e.suppressed.iter().enumerate() {
+        write_exception(out_line, s, &format!("Suppressed [{}]", i + 1), depth + 1, config);
+    }
+    if let Some(c) = &e.cause {
+        write_exception(out_line, c, &format!("Caused by [{}]", depth + 1), depth + 1, config);
+    }
+}
+
+fn write_collapsed(out_line: &mut String, indent: &str, count: usize, prefixes: &[&str]) {
+    if count > 0 {
+        let _ = writeln!(
+            out_line,
+            "{}\t ... {} frame{} in {}",
+            indent,
+            count,
+            if count == 1 { "" } else { "s" },
+            prefixes.join(", ")
+        );
+    }
+}
+
+fn style_str(b: bool, style: Style) -> String {
+    match style {
+        Style::Plain => color_str(b, &color::Reset),
+        Style::Good => color_str(b, &color::Green),
+        Style::Warning => color_str(b, &color::Yellow),
+        Style::Error => color_str(b, &color::Red),
+        Style::Highlight => color_str(b, &color::Blue),
     }
-    return out_line;
 }
 
 fn color_str(b: bool, c: &dyn color::Color) -> String {
     if b {
-        return color::Fg(c).to_string();
+        color::Fg(c).to_string()
     } else {
-        return String::from("");
+        String::from("")
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use grok::filter::GrepFilter;
+    use grok::format::Detector;
+    use grok::level::LevelFilter;
+    use grok::subscription::{DeliveredEvent, Delivery};
+    use termion::style;
+
+    use super::{Config, LiveInput, Printer};
+
+    fn config() -> Config {
+        Config {
+            nocolor: true,
+            level: LevelFilter::All,
+            emf: String::from("show"),
+            ..Default::default()
+        }
+    }
+
+    fn printer(config: Config) -> (Printer, Arc<Mutex<Vec<u8>>>) {
+        let out = Arc::new(Mutex::new(vec![]));
+        (Printer::with_output(config, out.clone()), out)
+    }
+
+    fn text(out: &Arc<Mutex<Vec<u8>>>) -> String {
+        String::from_utf8(out.lock().unwrap().clone()).unwrap()
+    }
+
     #[test]
     fn parse_line() {
         let input = "{\"thread\":\"CommunicationEngineWorker-5\",\"level\":\"WARN\",\"loggerName\":\"com.shopstyle.messaging.ce.core.CommunicationRequestProcessor\",\"message\":\"Task type [CBReengageFavorite] took longer than [120] seconds to execute. Elapsed time: [3.471 min] - Request: [com.shopstyle.messaging.model.ce.CommunicationRequest@462d2036[id=7c60a640-b61c-4e55-812a-237568e93fd6,created=Mon Dec 21 11:31:22 CST 2020,source=5fe0dbc37be10c2ddad8cd46,appName=shopstyle,locale=en_US,types=[CBReengageFavorite],recipients=[40726490],frequencies={CBReengageFavorite=Monday},startDates=<null>,targets={CBReengageFavorite=[Email]},attributes=<null>,limit=1]]\",\"endOfBatch\":false,\"loggerFqcn\":\"org.apache.logging.slf4j.Log4jLogger\",\"instant\":{\"epochSecond\":1608579508,\"nanoOfSecond\":964000000},\"contextMap\":{},\"threadId\":95,\"threadPriority\":5}";
 
-        let result = super::create_log_string(
-            "ALL".to_string(),
-            String::new(),
-            String::new(),
-            true,
-            input.to_string(),
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, input);
+        assert!(!result.is_empty());
+    }
+
+    #[test]
+    fn render_nested_causes() {
+        let frame = "{\"class\":\"com.example.Dao\",\"method\":\"save\",\"file\":\"Dao.java\",\"line\":42,\"exact\":false,\"location\":\"app.jar\",\"version\":\"?\"}";
+        let mut thrown = format!("{{\"commonElementCount\":0,\"name\":\"java.net.SocketException\",\"message\":\"Connection reset\",\"extendedStackTrace\":[{}]}}", frame);
+        for name in ["org.postgresql.util.PSQLException", "org.hibernate.exception.JDBCConnectionException", "org.springframework.dao.DataAccessResourceFailureException"] {
+            thrown = format!("{{\"commonElementCount\":0,\"name\":\"{}\",\"extendedStackTrace\":[{}],\"cause\":{}}}", name, frame, thrown);
+        }
+        thrown = thrown.replacen("\"extendedStackTrace\"", &format!("\"suppressed\":[{{\"commonElementCount\":0,\"name\":\"java.io.IOException\",\"message\":\"close failed\",\"extendedStackTrace\":[{}]}}],\"extendedStackTrace\"", frame), 1);
+        let input = format!("{{\"thread\":\"main\",\"level\":\"ERROR\",\"loggerName\":\"com.example.Service\",\"message\":\"save failed\",\"thrown\":{},\"endOfBatch\":false,\"loggerFqcn\":\"org.apache.logging.slf4j.Log4jLogger\",\"instant\":{{\"epochSecond\":1608579508,\"nanoOfSecond\":0}},\"threadId\":1,\"threadPriority\":5}}", thrown);
+
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, &input);
+        assert!(result.contains("Stacktrace: org.springframework.dao.DataAccessResourceFailureException - none"));
+        assert!(result.contains("  Suppressed [1]: java.io.IOException - close failed"));
+        assert!(result.contains("      Caused by [3]: java.net.SocketException - Connection reset"));
+        assert_eq!(result.matches("Dao.save").count(), 5);
+    }
+
+    #[test]
+    fn elide_common_and_collapsed_frames() {
+        let frame = |class: &str| format!("{{\"class\":\"{}\",\"method\":\"run\",\"line\":1,\"exact\":false,\"location\":\"app.jar\",\"version\":\"?\"}}", class);
+        let frames = [frame("com.example.Handler"), frame("sun.reflect.GeneratedMethodAccessor"), frame("org.springframework.aop.Proxy"), frame("org.apache.catalina.Valve")].join(",");
+        let input = format!("{{\"thread\":\"http-nio-8080-exec-1\",\"level\":\"ERROR\",\"loggerName\":\"com.example.Handler\",\"message\":\"failed\",\"thrown\":{{\"commonElementCount\":0,\"name\":\"java.lang.IllegalStateException\",\"extendedStackTrace\":[{0}],\"cause\":{{\"commonElementCount\":3,\"name\":\"java.io.IOException\",\"extendedStackTrace\":[{1},{0}]}}}},\"endOfBatch\":false,\"loggerFqcn\":\"org.apache.logging.slf4j.Log4jLogger\",\"instant\":{{\"epochSecond\":1608579508,\"nanoOfSecond\":0}},\"threadId\":1,\"threadPriority\":5}}", frames, frame("com.example.Io"));
+        let config = Config {
+            collapse: vec!["org.springframework.".to_string(), "sun.reflect.".to_string()],
+            ..config()
+        };
+
+        let result = super::create_log_string(&config, &mut Detector::default(), "", None, &input);
+        assert!(result.contains("\t ... 2 frames in sun.reflect., org.springframework.\n"));
+        assert!(result.contains("  \t ... 3 more\n"));
+        assert_eq!(result.matches("com.example.Handler.run").count(), 2);
+        assert_eq!(result.matches("org.apache.catalina.Valve.run").count(), 1);
+    }
+
+    #[test]
+    fn parse_minimal_log4j2_line() {
+        let input = "{\"timeMillis\":1608579508964,\"level\":\"INFO\",\"loggerName\":\"com.example.Service\",\"message\":\"started\",\"service\":\"orders\"}";
+
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, input);
+        assert!(result.contains("2020-12-21 19:38:28 [-] INFO com.example.Service - started"));
+        assert!(result.contains("\t service = orders"));
+    }
+
+    #[test]
+    fn omit_unknown_log4j2_frame_parts() {
+        let input = r#"{"timeMillis":1608579508964,"level":"ERROR","loggerName":"com.example.Service","message":"failed","thrown":{"name":"java.lang.IllegalStateException","extendedStackTrace":[{"class":"com.example.Service","method":"run","file":"Service.java"}]}}"#;
+
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, input);
+        assert!(result.contains("\t at com.example.Service.run (Service.java)\n"));
+    }
+
+    #[test]
+    fn parse_logstash_line() {
+        let input = r#"{"@timestamp":"2020-12-21T13:38:28.964-06:00","@version":"1","message":"save failed","logger_name":"com.example.Dao","thread_name":"main","level":"ERROR","level_value":40000,"requestId":"abc","stack_trace":"java.lang.IllegalStateException: boom\n\tat com.example.Dao.save(Dao.java:42)\n"}"#;
+
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, input);
+        assert!(result.contains("2020-12-21 19:38:28 [main] ERROR com.example.Dao - save failed"));
+        assert!(result.contains("\t requestId = abc"));
+        assert!(result.contains("Stacktrace: java.lang.IllegalStateException - boom"));
+        assert!(result.contains("\t at com.example.Dao.save (Dao.java:42)"));
+    }
+
+    #[test]
+    fn keep_unparsed_logstash_stack_trace() {
+        let input = r#"{"@timestamp":"2020-12-21T13:38:28.964-06:00","message":"save failed","level":"ERROR","stack_trace":"<#1a2b3c4d> c.e.Dao.save"}"#;
+
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, input);
+        assert!(result.contains("\t stack_trace = <#1a2b3c4d> c.e.Dao.save"));
+    }
+
+    #[test]
+    fn keep_structlog_dict_traceback() {
+        let input = r#"{"event":"job failed","level":"error","timestamp":"2021-03-04T10:11:12Z","exception":[{"exc_type":"KeyError","exc_value":"'id'"}]}"#;
+
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, input);
+        assert!(result.contains("job failed"));
+        assert!(result.contains(r#"exception = [{"exc_type":"KeyError","exc_value":"'id'"}]"#));
+    }
+
+    #[test]
+    fn parse_pino_line() {
+        let input = r#"{"level":50,"time":1608579508964,"pid":7,"hostname":"ip-10-0-0-1","name":"orders","msg":"lookup failed","err":{"type":"TypeError","message":"bad id","stack":"TypeError: bad id\n    at handler (/var/task/index.js:10:15)"}}"#;
+        let config = Config {
+            level: LevelFilter::parse("ERROR").unwrap(),
+            ..config()
+        };
+
+        let result = super::create_log_string(&config, &mut Detector::default(), "", None, input);
+        assert!(result.contains("2020-12-21 19:38:28 [-] ERROR orders - lookup failed"));
+        assert!(result.contains("\t hostname = ip-10-0-0-1"));
+        assert!(result.contains("Stacktrace: TypeError - bad id"));
+        assert!(result.contains("\t at handler (/var/task/index.js:10)"));
+    }
+
+    #[test]
+    fn parse_zap_line() {
+        let input = r#"{"level":"error","ts":1608579508.964,"caller":"api/server.go:42","msg":"request failed","error":"timeout","stacktrace":"github.com/acme/svc/api.(*Server).Handle\n\t/app/api/server.go:42\nmain.main\n\t/app/main.go:10"}"#;
+
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, input);
+        assert!(result.contains("2020-12-21 19:38:28 [-] ERROR api/server.go:42 - request failed"));
+        assert!(result.contains("Stacktrace: error - timeout"));
+        assert!(result.contains("\t at github.com/acme/svc/api.(*Server).Handle (/app/api/server.go:42)\n\t at main.main (/app/main.go:10)"));
+    }
+
+    #[test]
+    fn parse_zerolog_line() {
+        let input = r#"{"level":"error","time":"2020-12-21T19:38:28Z","message":"save failed","error":"disk full","stack":[{"func":"(*Store).Save","line":"12","source":"store.go"}]}"#;
+
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, input);
+        assert!(result.contains("2020-12-21 19:38:28 [-] ERROR  - save failed"));
+        assert!(result.contains("Stacktrace: error - disk full"));
+        assert!(result.contains("\t at (*Store).Save (store.go:12)"));
+
+        // without a stack, the error is just another field
+        let input = r#"{"level":"error","time":"2020-12-21T19:38:28Z","message":"save failed","error":"disk full"}"#;
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, input);
+        assert!(result.contains("\t error = disk full"));
+        assert!(!result.contains("Stacktrace"));
+    }
+
+    #[test]
+    fn parse_logrus_line() {
+        let input = r#"{"level":"warning","time":"2020-12-21T13:38:28-06:00","msg":"slow query","file":"db/query.go:88","duration":"3.2s"}"#;
+
+        let result = super::create_log_string(&config(), &mut Detector::default(), "", None, input);
+        assert!(result.contains("2020-12-21 19:38:28 [-] WARN db/query.go:88 - slow query"));
+        assert!(result.contains("\t duration = 3.2s"));
+    }
+
+    #[test]
+    fn group_invocations() {
+        let (mut printer, out) = printer(Config {
+            group: String::from("/aws/lambda/orders"),
+            invocations: true,
+            ..config()
+        });
+        printer.print("s1", None, "START RequestId: 8f5b7b6e Version: $LATEST");
+        printer.print("s2", None, "START RequestId: 0c2b8d1c Version: $LATEST");
+        printer.print("s1", None, "charging card");
+        printer.print("s2", None, "refund requested");
+        printer.print("s1", None, "END RequestId: 8f5b7b6e");
+        printer.print("s1", None, "REPORT RequestId: 8f5b7b6e\tDuration: 102.25 ms\tBilled Duration: 103 ms\tMemory Size: 128 MB\tMax Memory Used: 70 MB\tInit Duration: 150.31 ms\t");
+        printer.finish();
+
+        let lines: Vec<String> = text(&out).lines().map(String::from).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "/aws/lambda/orders s1 == Invocation 8f5b7b6e 102.25 ms (billed 103 ms), 70/128 MB, cold start (init 150.31 ms)",
+                "/aws/lambda/orders s1 -- charging card",
+                "/aws/lambda/orders s2 == Invocation 0c2b8d1c (incomplete)",
+                "/aws/lambda/orders s2 -- refund requested",
+            ]
         );
-        assert_eq!(result.is_empty(), false);
+    }
+
+    #[test]
+    fn skip_invocations_without_matches() {
+        let grep = GrepFilter::new(&[String::from("refund")], &[], false).unwrap();
+        let (mut printer, out) = printer(Config {
+            invocations: true,
+            grep,
+            ..config()
+        });
+        for (request_id, line) in [("8f5b7b6e", "charging card"), ("0c2b8d1c", "refund requested")] {
+            printer.print("s1", None, &format!("START RequestId: {} Version: $LATEST", request_id));
+            printer.print("s1", None, line);
+            printer.print("s1", None, &format!("REPORT RequestId: {}\tDuration: 2.5 ms\tBilled Duration: 3 ms\tMemory Size: 128 MB\tMax Memory Used: 70 MB\t", request_id));
+        }
+        printer.finish();
+
+        let text = text(&out);
+        assert!(!text.contains("8f5b7b6e"));
+        assert!(text.starts_with(" s1 == Invocation 0c2b8d1c 2.5 ms (billed 3 ms), 70/128 MB\n"));
+        assert!(text.contains("refund requested"));
+    }
+
+    #[test]
+    fn print_delivery_timestamps() {
+        let (mut printer, out) = printer(Config {
+            group: String::from("/ecs/orders"),
+            ..config()
+        });
+        let delivery = Delivery {
+            message_type: String::from("DATA_MESSAGE"),
+            log_group: String::from("/ecs/orders"),
+            log_stream: String::from("web/1"),
+            log_events: vec![
+                DeliveredEvent {
+                    id: String::from("1"),
+                    timestamp: 1608579508964,
+                    message: String::from("listening on :8080"),
+                },
+                DeliveredEvent {
+                    id: String::from("2"),
+                    timestamp: 1608579509964,
+                    message: String::from(r#"{"levelname":"INFO","name":"orders","message":"order placed"}"#),
+                },
+            ],
+        };
+        super::print_deliveries(&mut printer, vec![delivery]);
+
+        let text = text(&out);
+        assert!(text.contains("/ecs/orders web/1 -- 2020-12-21 19:38:28 listening on :8080\n"));
+        assert!(text.contains("/ecs/orders web/1 -- 2020-12-21 19:38:29 [-] INFO orders - order placed"));
+    }
+
+    #[test]
+    fn read_pretty_printed_export_from_stdin() {
+        let (mut printer, out) = printer(config());
+        let (sender, lines) = mpsc::channel();
+        let document = "{\n    \"events\": [\n        {\n            \"logStreamName\": \"web/1\",\n            \"timestamp\": 1608579508964,\n            \"message\": \"listening on :8080\"\n        }\n    ]\n}\n";
+        for line in document.split_inclusive('\n') {
+            sender.send(line.as_bytes().to_vec()).unwrap();
+        }
+        drop(sender);
+
+        super::read_input(&mut printer, "", LiveInput::new(lines, Duration::from_millis(50)));
+        assert_eq!(text(&out), " web/1 -- 2020-12-21 19:38:28 listening on :8080\n");
+    }
+
+    const EMF_LINE: &str = r#"{"_aws":{"Timestamp":1608579508964,"CloudWatchMetrics":[{"Namespace":"Orders","Dimensions":[["Service"]],"Metrics":[{"Name":"Latency","Unit":"Milliseconds"}]}]},"Service":"orders","Latency":[12.5,3]}"#;
+
+    #[test]
+    fn route_emf_lines() {
+        for (emf, shown) in [("show", vec!["Latency", "order placed"]), ("hide", vec!["order placed"]), ("only", vec!["Latency"])] {
+            let (mut printer, out) = printer(Config {
+                emf: String::from(emf),
+                ..config()
+            });
+            printer.print("", None, EMF_LINE);
+            printer.print("", None, "order placed");
+            printer.finish();
+
+            let text = text(&out);
+            assert_eq!(text.lines().count(), shown.len(), "--emf {}", emf);
+            for line in shown {
+                assert!(text.contains(line), "--emf {}", emf);
+            }
+        }
+    }
+
+    #[test]
+    fn total_emf_metrics() {
+        let (mut printer, out) = printer(Config {
+            metrics: true,
+            ..config()
+        });
+        printer.print("", None, EMF_LINE);
+        printer.print("", None, &EMF_LINE.replace("[12.5,3]", "4.5"));
+        printer.print("", None, "order placed");
+        printer.finish();
+
+        assert_eq!(text(&out), "Orders Service=orders Latency: sum=20 count=3 min=3 max=12.5 avg=6.666666666666667 Milliseconds\n");
+    }
+
+    #[test]
+    fn grep_and_highlight() {
+        let config = Config {
+            nocolor: false,
+            grep: GrepFilter::new(&[String::from("time(out)?")], &[String::from("health")], true).unwrap(),
+            ..config()
+        };
+        let line = r#"{"timeMillis":1608579508964,"level":"WARN","loggerName":"com.example.Client","message":"request TIMEOUT","path":"/orders"}"#;
+
+        let result = super::create_log_string(&config, &mut Detector::default(), "", None, line);
+        assert!(result.contains(&format!("request {}TIMEOUT{}", style::Invert, style::NoInvert)));
+        assert!(super::create_log_string(&config, &mut Detector::default(), "", None, &line.replace("/orders", "/health")).is_empty());
+        assert!(super::create_log_string(&config, &mut Detector::default(), "", None, "plain line").is_empty());
+    }
+
+    #[test]
+    fn flush_event_when_input_pauses() {
+        let (mut printer, out) = printer(config());
+        let (sender, lines) = mpsc::channel();
+        let feeder = {
+            let out = out.clone();
+            thread::spawn(move || {
+                sender.send(b"10:00:00 ERROR svc - failed\n".to_vec()).unwrap();
+                thread::sleep(Duration::from_millis(300));
+                let flushed = text(&out).contains("failed");
+                sender.send(b"10:00:01 INFO svc - next\n".to_vec()).unwrap();
+                flushed
+            })
+        };
+
+        super::read_lines(&mut printer, "", LiveInput::new(lines, Duration::from_millis(50)));
+        assert!(feeder.join().unwrap());
+        assert_eq!(text(&out).lines().count(), 2);
     }
 }
@@ -0,0 +1,114 @@
+//! Reassembly of multi-line events (stack traces, tracebacks) from physical input lines.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+fn exception_header_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(?:[\w$]+\.)+[\w$]*(?:Exception|Error|Throwable)(?::\s.*)?$").unwrap())
+}
+
+const CONTINUATIONS: [&str; 6] = [
+    "at ",
+    "Caused by:",
+    "Suppressed:",
+    "... ",
+    "During handling of the above exception",
+    "The above exception was the direct cause",
+];
+
+/// Groups physical lines into logical events. By default a line continues the current event when
+/// it is indented, blank, or looks like part of a Java or Python stack trace; with a `start`
+/// regex, every line that doesn't match it is a continuation.
+pub struct Reassembler {
+    start: Option<Regex>,
+    pending: Option<String>,
+    in_traceback: bool,
+}
+
+impl Reassembler {
+    pub fn new(start: Option<Regex>) -> Reassembler {
+        Reassembler {
+            start,
+            pending: None,
+            in_traceback: false,
+        }
+    }
+
+    /// Adds a line, returning the previous event when `line` starts a new one.
+    pub fn push(&mut self, line: String) -> Option<String> {
+        if self.pending.is_some() && self.is_continuation(&line) {
+            let pending = self.pending.as_mut().unwrap();
+            pending.push('\n');
+            pending.push_str(&line);
+            return None;
+        }
+        self.in_traceback = line.trim_start().starts_with("Traceback (most recent call last)");
+        self.pending.replace(line).map(trim_event)
+    }
+
+    /// Returns the event still being assembled, if any.
+    pub fn flush(&mut self) -> Option<String> {
+        self.in_traceback = false;
+        self.pending.take().map(trim_event)
+    }
+
+    fn is_continuation(&mut self, line: &str) -> bool {
+        if let Some(start) = &self.start {
+            return !start.is_match(line);
+        }
+        let trimmed = line.trim();
+        if trimmed.starts_with("Traceback (most recent call last)") {
+            self.in_traceback = true;
+            return true;
+        }
+        if trimmed.is_empty() || line.starts_with(char::is_whitespace) {
+            return true;
+        }
+        if self.in_traceback && !trimmed.starts_with('{') {
+            // the unindented "ValueError: ..." line that ends a traceback
+            self.in_traceback = false;
+            return true;
+        }
+        CONTINUATIONS.iter().any(|c| trimmed.starts_with(c)) || exception_header_re().is_match(trimmed)
+    }
+}
+
+fn trim_event(mut event: String) -> String {
+    event.truncate(event.trim_end().len());
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::Reassembler;
+
+    fn reassemble(lines: &str, start: Option<Regex>) -> Vec<String> {
+        let mut reassembler = Reassembler::new(start);
+        let mut events: Vec<String> = lines.lines().filter_map(|l| reassembler.push(l.to_string())).collect();
+        events.extend(reassembler.flush());
+        events
+    }
+
+    #[test]
+    fn group_stack_traces() {
+        let lines = "10:00:00 ERROR svc - failed\njava.lang.IllegalStateException: boom\n\tat com.example.Svc.run(Svc.java:1)\nCaused by: java.io.IOException\n\t... 3 more\n10:00:01 INFO svc - next\nTraceback (most recent call last):\n  File \"x.py\", line 1, in <module>\nValueError: bad\n\n10:00:02 INFO svc - done";
+
+        let events = reassemble(lines, None);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].lines().count(), 5);
+        assert!(events[1].ends_with("ValueError: bad"));
+        assert_eq!(events[2], "10:00:02 INFO svc - done");
+    }
+
+    #[test]
+    fn group_by_start_regex() {
+        let lines = "[2024-01-01] first\ncontinued\n[2024-01-02] second";
+
+        let events = reassemble(lines, Some(Regex::new(r"^\[\d{4}-").unwrap()));
+        assert_eq!(events, vec!["[2024-01-01] first\ncontinued", "[2024-01-02] second"]);
+    }
+}
@@ -0,0 +1,115 @@
+//! Payloads delivered by CloudWatch Logs subscription filters to Kinesis, Firehose or Lambda:
+//! gzip-compressed JSON, base64 encoded when taken from a Kinesis record or Lambda event.
+
+use std::io::Read;
+use std::sync::OnceLock;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use flate2::read::MultiGzDecoder;
+use regex::Regex;
+use serde::Deserialize;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// How gzip data starts once base64 encoded.
+const BASE64_GZIP: &str = "H4sI";
+
+fn payload_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"^\s*\{\s*"messageType"\s*:"#).unwrap())
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Delivery {
+    /// `DATA_MESSAGE`, or `CONTROL_MESSAGE` for the checks CloudWatch sends when a filter is
+    /// created.
+    pub message_type: String,
+    #[serde(default)]
+    pub log_group: String,
+    #[serde(default)]
+    pub log_stream: String,
+    #[serde(default)]
+    pub log_events: Vec<DeliveredEvent>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct DeliveredEvent {
+    pub id: String,
+    pub timestamp: i64,
+    pub message: String,
+}
+
+/// Whether the input starting with `head` looks like subscription payloads rather than log lines.
+pub fn is_payload(head: &[u8]) -> bool {
+    head.starts_with(&GZIP_MAGIC) || {
+        let text = String::from_utf8_lossy(head);
+        text.trim_start().starts_with(BASE64_GZIP) || payload_re().is_match(&text)
+    }
+}
+
+/// Decodes the data deliveries of `input`: gzip data (i.e. a Firehose object in S3), base64
+/// payloads one per line, or decoded JSON payloads. Control messages are skipped.
+pub fn decode(input: &[u8]) -> Result<Vec<Delivery>, String> {
+    let mut deliveries = vec![];
+    if input.starts_with(&GZIP_MAGIC) {
+        let mut text = String::new();
+        MultiGzDecoder::new(input)
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Unable to decompress payload: {}", e))?;
+        return decode(text.as_bytes());
+    }
+    let text = String::from_utf8_lossy(input);
+    if text.trim_start().starts_with(BASE64_GZIP) {
+        for line in text.split_whitespace() {
+            let data = STANDARD.decode(line).map_err(|e| format!("Invalid base64 payload: {}", e))?;
+            deliveries.extend(decode(&data)?);
+        }
+        return Ok(deliveries);
+    }
+    // Firehose concatenates payloads without a separator
+    for delivery in serde_json::Deserializer::from_str(&text).into_iter::<Delivery>() {
+        let delivery = delivery.map_err(|e| format!("Invalid payload: {}", e))?;
+        if delivery.message_type == "DATA_MESSAGE" {
+            deliveries.push(delivery);
+        }
+    }
+    Ok(deliveries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::{decode, is_payload};
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn decode_payloads() {
+        let data = r#"{"messageType":"DATA_MESSAGE","owner":"123456789012","logGroup":"/aws/lambda/orders","logStream":"s1","subscriptionFilters":["f"],"logEvents":[{"id":"1","timestamp":1704067200000,"message":"hello"}]}"#;
+        let control = r#"{"messageType":"CONTROL_MESSAGE","owner":"CloudwatchLogs","logGroup":"","logStream":"","subscriptionFilters":[],"logEvents":[{"id":"","timestamp":1704067200000,"message":"CWL CONTROL MESSAGE: Checking health of destination Firehose."}]}"#;
+
+        let lines = format!("{}\n{}\n", STANDARD.encode(gzip(data)), STANDARD.encode(gzip(control)));
+        assert!(is_payload(lines.as_bytes()));
+        let deliveries = decode(lines.as_bytes()).unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].log_group, "/aws/lambda/orders");
+        assert_eq!(deliveries[0].log_events[0].message, "hello");
+
+        let firehose = gzip(&format!("{}{}{}", data, control, data));
+        assert!(is_payload(&firehose));
+        assert_eq!(decode(&firehose).unwrap().len(), 2);
+        assert!(!is_payload(br#"{"level":"INFO"}"#));
+    }
+}